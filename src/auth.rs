@@ -0,0 +1,927 @@
+use crate::{Error, HttpRequest, HttpResponse, HttpTransport, Result};
+use anyhow::Context as _;
+use async_trait::async_trait;
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::Path;
+#[cfg(feature = "reqwest")]
+use std::path::PathBuf;
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+
+/// OAuth scope requested for access tokens.
+pub const CLOUD_PLATFORM_SCOPE: &str = "https://www.googleapis.com/auth/cloud-platform";
+
+/// Tokens are refreshed this long before they expire, so they don't run out in flight.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+/// Lifetime requested for self-signed JWTs, the maximum Google accepts.
+const JWT_LIFETIME: Duration = Duration::from_secs(3600);
+
+const GOOGLE_TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+
+const METADATA_HOST: &str = "metadata.google.internal";
+
+/// How long to wait for the metadata server before concluding we're not on Google Cloud.
+const METADATA_PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The JSON key file of a service account, as downloaded from the Cloud Console.
+#[derive(Deserialize, Clone)]
+pub struct ServiceAccountKey {
+    pub client_email: String,
+    pub private_key_id: Option<String>,
+    pub private_key: String,
+    pub token_uri: String,
+    pub project_id: Option<String>,
+}
+
+impl std::fmt::Debug for ServiceAccountKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServiceAccountKey")
+            .field("client_email", &self.client_email)
+            .field("private_key_id", &self.private_key_id)
+            .field("token_uri", &self.token_uri)
+            .field("project_id", &self.project_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// User credentials written by `gcloud auth application-default login`.
+#[derive(Deserialize, Clone)]
+pub struct AuthorizedUserKey {
+    pub client_id: String,
+    pub client_secret: String,
+    pub refresh_token: String,
+    pub quota_project_id: Option<String>,
+}
+
+impl std::fmt::Debug for AuthorizedUserKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuthorizedUserKey")
+            .field("client_id", &self.client_id)
+            .field("quota_project_id", &self.quota_project_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A credentials file as found by Application Default Credentials.
+#[cfg(feature = "reqwest")]
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum CredentialsFile {
+    ServiceAccount(ServiceAccountKey),
+    AuthorizedUser(AuthorizedUserKey),
+    ImpersonatedServiceAccount {
+        service_account_impersonation_url: String,
+        source_credentials: Box<CredentialsFile>,
+        #[serde(default)]
+        delegates: Vec<String>,
+    },
+}
+
+#[cfg(feature = "reqwest")]
+impl CredentialsFile {
+    fn into_token_provider(self) -> anyhow::Result<Arc<dyn TokenProvider>> {
+        Ok(match self {
+            CredentialsFile::ServiceAccount(key) => Arc::new(ServiceAccount::new(key)?),
+            CredentialsFile::AuthorizedUser(key) => Arc::new(AuthorizedUser::new(key)),
+            CredentialsFile::ImpersonatedServiceAccount {
+                service_account_impersonation_url,
+                source_credentials,
+                delegates,
+            } => {
+                let source = source_credentials.into_token_provider()?;
+                let delegates: Vec<_> = delegates.iter().map(String::as_str).collect();
+
+                Arc::new(
+                    ImpersonatedServiceAccount::with_uri(source, service_account_impersonation_url)
+                        .delegates(&delegates),
+                )
+            }
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct Claims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: u64,
+    exp: u64,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+struct CachedToken {
+    token: String,
+    expires_at: Instant,
+}
+
+/// The last access token of a source, shared by concurrent requests.
+#[derive(Default)]
+struct TokenCache(Mutex<Option<CachedToken>>);
+
+impl TokenCache {
+    /// The cached token, or a new one from `refresh` if it is about to expire.
+    async fn get_or_refresh<F, Fut>(&self, refresh: F) -> Result<String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = anyhow::Result<CachedToken>>,
+    {
+        let mut cached = self.0.lock().await;
+
+        if let Some(token) = cached.as_ref() {
+            if token.expires_at > Instant::now() + EXPIRY_MARGIN {
+                return Ok(token.token.clone());
+            }
+        }
+
+        let token = refresh().await.map_err(Error::auth)?;
+        let access_token = token.token.clone();
+        *cached = Some(token);
+
+        Ok(access_token)
+    }
+
+    /// Drops the cached token, so the next request fetches a new one.
+    async fn invalidate(&self) {
+        *self.0.lock().await = None;
+    }
+}
+
+/// The transport a provider fetches tokens with, replaced by the one of the
+/// [`Client`](crate::Client) it is used with, see [`TokenProvider::use_transport`].
+struct TokenHttp(RwLock<Option<Arc<dyn HttpTransport>>>);
+
+impl Default for TokenHttp {
+    fn default() -> Self {
+        #[cfg(feature = "reqwest")]
+        let transport: Option<Arc<dyn HttpTransport>> = Some(Arc::new(reqwest::Client::new()));
+        #[cfg(not(feature = "reqwest"))]
+        let transport = None;
+
+        Self(RwLock::new(transport))
+    }
+}
+
+impl TokenHttp {
+    /// Sends `request` with the transport, failing if there is none yet.
+    async fn send(&self, request: HttpRequest) -> anyhow::Result<HttpResponse> {
+        let transport = self
+            .0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+            .context("no HTTP transport to fetch tokens with; use the provider with a Client")?;
+
+        Ok(transport.send(request).await?)
+    }
+
+    fn set(&self, transport: &Arc<dyn HttpTransport>) {
+        *self.0.write().unwrap_or_else(PoisonError::into_inner) = Some(transport.clone());
+    }
+}
+
+/// Sends a token request and reads the standard OAuth token response.
+async fn fetch_token(
+    http: &TokenHttp,
+    request: HttpRequest,
+    what: &str,
+) -> anyhow::Result<CachedToken> {
+    let requested_at = Instant::now();
+    let response = http.send(request).await?;
+
+    let body = String::from_utf8_lossy(&response.body);
+    anyhow::ensure!(
+        (200..300).contains(&response.status),
+        "token request for {} failed with {}: {}",
+        what,
+        response.status,
+        body
+    );
+
+    let token: TokenResponse = serde_json::from_str(&body)?;
+
+    Ok(CachedToken {
+        token: token.access_token,
+        expires_at: requested_at + Duration::from_secs(token.expires_in),
+    })
+}
+
+/// Access tokens for a service account, obtained by exchanging a self-signed JWT
+/// and refreshed automatically shortly before they expire.
+pub struct ServiceAccount {
+    key: ServiceAccountKey,
+    encoding_key: EncodingKey,
+    http: TokenHttp,
+    cache: TokenCache,
+}
+
+impl ServiceAccount {
+    pub fn new(key: ServiceAccountKey) -> Result<Self> {
+        let encoding_key =
+            EncodingKey::from_rsa_pem(key.private_key.as_bytes()).map_err(|error| {
+                Error::auth(
+                    anyhow::Error::new(error).context("invalid service account private key"),
+                )
+            })?;
+
+        Ok(Self {
+            key,
+            encoding_key,
+            http: TokenHttp::default(),
+            cache: TokenCache::default(),
+        })
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        let key = serde_json::from_str(json)
+            .context("invalid service account key")
+            .map_err(Error::auth)?;
+
+        Self::new(key)
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))
+            .map_err(Error::auth)?;
+
+        Self::from_json(&json)
+    }
+
+    pub fn key(&self) -> &ServiceAccountKey {
+        &self.key
+    }
+
+    /// A valid access token, fetching a new one if the cached one is about to expire.
+    pub async fn access_token(&self) -> Result<String> {
+        self.cache
+            .get_or_refresh(|| async {
+                let assertion = self.jwt(SystemTime::now())?;
+                let request = HttpRequest::post_form(
+                    &self.key.token_uri,
+                    &[
+                        ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                        ("assertion", &assertion),
+                    ],
+                );
+
+                fetch_token(&self.http, request, &self.key.client_email).await
+            })
+            .await
+    }
+
+    fn jwt(&self, now: SystemTime) -> anyhow::Result<String> {
+        let iat = now.duration_since(UNIX_EPOCH)?.as_secs();
+        let claims = Claims {
+            iss: &self.key.client_email,
+            scope: CLOUD_PLATFORM_SCOPE,
+            aud: &self.key.token_uri,
+            iat,
+            exp: iat + JWT_LIFETIME.as_secs(),
+        };
+        let header = Header {
+            kid: self.key.private_key_id.clone(),
+            ..Header::new(Algorithm::RS256)
+        };
+
+        Ok(jsonwebtoken::encode(&header, &claims, &self.encoding_key)?)
+    }
+}
+
+/// Access tokens for a user, refreshed with the refresh token from
+/// `gcloud auth application-default login`.
+pub struct AuthorizedUser {
+    key: AuthorizedUserKey,
+    http: TokenHttp,
+    cache: TokenCache,
+}
+
+impl AuthorizedUser {
+    pub fn new(key: AuthorizedUserKey) -> Self {
+        Self {
+            key,
+            http: TokenHttp::default(),
+            cache: TokenCache::default(),
+        }
+    }
+
+    pub fn key(&self) -> &AuthorizedUserKey {
+        &self.key
+    }
+
+    /// A valid access token, fetching a new one if the cached one is about to expire.
+    pub async fn access_token(&self) -> Result<String> {
+        self.cache
+            .get_or_refresh(|| {
+                let request = HttpRequest::post_form(
+                    GOOGLE_TOKEN_URI,
+                    &[
+                        ("grant_type", "refresh_token"),
+                        ("client_id", &self.key.client_id),
+                        ("client_secret", &self.key.client_secret),
+                        ("refresh_token", &self.key.refresh_token),
+                    ],
+                );
+
+                fetch_token(&self.http, request, &self.key.client_id)
+            })
+            .await
+    }
+}
+
+/// Access tokens from the metadata server of the GCE instance, GKE pod or Cloud Run
+/// service the process runs on. With GKE Workload Identity these are the tokens of
+/// the Google service account bound to the pod's Kubernetes service account.
+///
+/// The server is reached at `metadata.google.internal`, or at the host named by
+/// `GCE_METADATA_HOST` if that is set.
+pub struct MetadataServer {
+    host: String,
+    service_account: String,
+    scopes: Vec<String>,
+    http: TokenHttp,
+    cache: TokenCache,
+}
+
+impl Default for MetadataServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MetadataServer {
+    /// Tokens of the instance's default service account.
+    pub fn new() -> Self {
+        Self {
+            host: std::env::var("GCE_METADATA_HOST").unwrap_or_else(|_| METADATA_HOST.to_string()),
+            service_account: "default".to_string(),
+            scopes: vec![],
+            http: TokenHttp::default(),
+            cache: TokenCache::default(),
+        }
+    }
+
+    /// Tokens of another service account attached to the instance, by email.
+    pub fn service_account(mut self, email: &str) -> Self {
+        self.service_account = email.to_string();
+        self
+    }
+
+    /// Requests tokens with these scopes instead of the instance's scopes.
+    pub fn scopes(mut self, scopes: &[&str]) -> Self {
+        self.scopes = scopes.iter().map(|scope| scope.to_string()).collect();
+        self
+    }
+
+    fn token_uri(&self) -> String {
+        let mut uri = format!(
+            "http://{}/computeMetadata/v1/instance/service-accounts/{}/token",
+            self.host, self.service_account
+        );
+        if !self.scopes.is_empty() {
+            uri.push_str("?scopes=");
+            uri.push_str(&self.scopes.join(","));
+        }
+        uri
+    }
+
+    /// Whether the metadata server answers, i.e. whether we run on Google Cloud.
+    pub async fn is_available(&self) -> bool {
+        let request =
+            HttpRequest::get(format!("http://{}", self.host)).header("Metadata-Flavor", "Google");
+        let response = tokio::time::timeout(METADATA_PROBE_TIMEOUT, self.http.send(request)).await;
+
+        matches!(response, Ok(Ok(response)) if response.header("Metadata-Flavor").is_some())
+    }
+
+    /// A valid access token, fetching a new one if the cached one is about to expire.
+    pub async fn access_token(&self) -> Result<String> {
+        self.cache
+            .get_or_refresh(|| {
+                let request =
+                    HttpRequest::get(self.token_uri()).header("Metadata-Flavor", "Google");
+
+                fetch_token(&self.http, request, &self.service_account)
+            })
+            .await
+    }
+}
+
+/// A source of OAuth access tokens for [`Client`](crate::Client).
+///
+/// Implement this to plug in tokens from another auth library, a secrets manager or a
+/// corporate token service.
+#[async_trait]
+pub trait TokenProvider: Send + Sync {
+    /// A token that is valid for at least the next request.
+    async fn token(&self) -> Result<String>;
+
+    /// Called when the API rejected the last token, e.g. because it was revoked before
+    /// it expired. Return `true` if [`TokenProvider::token`] will return a new one, and
+    /// the request is retried once.
+    async fn invalidate(&self) -> bool {
+        false
+    }
+
+    /// Called with the transport of each [`Client`](crate::Client) the provider is used
+    /// with, e.g. one with the timeouts and proxies of a
+    /// [`ClientBuilder`](crate::ClientBuilder). Providers that fetch tokens over HTTP
+    /// should send those requests with the transport last given here.
+    fn use_transport(&self, _transport: &Arc<dyn HttpTransport>) {}
+}
+
+/// Access tokens of a target service account, generated with the IAM Credentials
+/// `generateAccessToken` method by a source identity that holds
+/// `roles/iam.serviceAccountTokenCreator` on it. No key of the target is needed.
+pub struct ImpersonatedServiceAccount {
+    source: Arc<dyn TokenProvider>,
+    uri: String,
+    delegates: Vec<String>,
+    scopes: Vec<String>,
+    lifetime: Duration,
+    http: TokenHttp,
+    cache: TokenCache,
+}
+
+#[derive(Serialize)]
+struct GenerateAccessTokenRequest<'a> {
+    scope: &'a [String],
+    delegates: &'a [String],
+    lifetime: String,
+}
+
+#[derive(Deserialize)]
+struct GenerateAccessTokenResponse {
+    #[serde(rename = "accessToken")]
+    access_token: String,
+}
+
+impl ImpersonatedServiceAccount {
+    /// Impersonates the service account `target` (an email) using tokens of `source`.
+    pub fn new(source: Arc<dyn TokenProvider>, target: &str) -> Self {
+        Self::with_uri(
+            source,
+            format!(
+                "https://iamcredentials.googleapis.com/v1/projects/-/serviceAccounts/{}:generateAccessToken",
+                target
+            ),
+        )
+    }
+
+    fn with_uri(source: Arc<dyn TokenProvider>, uri: String) -> Self {
+        Self {
+            source,
+            uri,
+            delegates: vec![],
+            scopes: vec![CLOUD_PLATFORM_SCOPE.to_string()],
+            lifetime: JWT_LIFETIME,
+            http: TokenHttp::default(),
+            cache: TokenCache::default(),
+        }
+    }
+
+    /// Service accounts in a delegation chain between the source and the target, by email.
+    pub fn delegates(mut self, delegates: &[&str]) -> Self {
+        self.delegates = delegates
+            .iter()
+            .map(|delegate| match delegate.starts_with("projects/") {
+                true => delegate.to_string(),
+                false => format!("projects/-/serviceAccounts/{}", delegate),
+            })
+            .collect();
+        self
+    }
+
+    pub fn scopes(mut self, scopes: &[&str]) -> Self {
+        self.scopes = scopes.iter().map(|scope| scope.to_string()).collect();
+        self
+    }
+
+    /// Lifetime of the generated tokens, at most one hour unless the organization
+    /// allows longer.
+    pub fn lifetime(mut self, lifetime: Duration) -> Self {
+        self.lifetime = lifetime;
+        self
+    }
+
+    /// A valid access token, fetching a new one if the cached one is about to expire.
+    pub async fn access_token(&self) -> Result<String> {
+        self.cache
+            .get_or_refresh(|| async {
+                let requested_at = Instant::now();
+                let body = serde_json::to_vec(&GenerateAccessTokenRequest {
+                    scope: &self.scopes,
+                    delegates: &self.delegates,
+                    lifetime: format!("{}s", self.lifetime.as_secs()),
+                })?;
+                let request = HttpRequest::post_json(&self.uri, body).header(
+                    "Authorization",
+                    &format!("Bearer {}", self.source.token().await?),
+                );
+                let response = self.http.send(request).await?;
+
+                let body = String::from_utf8_lossy(&response.body);
+                anyhow::ensure!(
+                    (200..300).contains(&response.status),
+                    "generateAccessToken ({}) failed with {}: {}",
+                    self.uri,
+                    response.status,
+                    body
+                );
+
+                let token: GenerateAccessTokenResponse = serde_json::from_str(&body)?;
+
+                Ok(CachedToken {
+                    token: token.access_token,
+                    expires_at: requested_at + self.lifetime,
+                })
+            })
+            .await
+    }
+}
+
+/// How [`Client`](crate::Client) authenticates its requests.
+#[derive(Clone)]
+pub enum Credential {
+    /// An API key from the Cloud Console, sent as the `key` query parameter.
+    ApiKey(String),
+    /// OAuth access tokens, sent as an `Authorization: Bearer` header.
+    Bearer(Arc<dyn TokenProvider>),
+}
+
+impl Credential {
+    /// A fixed OAuth access token, e.g. from `gcloud auth print-access-token`.
+    pub fn access_token(token: &str) -> Self {
+        Credential::Bearer(Arc::new(StaticToken(token.to_string())))
+    }
+
+    /// Adds the credential to a request.
+    pub(crate) async fn authorize(&self, request: &mut HttpRequest) -> Result<()> {
+        match self {
+            Credential::ApiKey(key) => {
+                let mut url = url::Url::parse(&request.url)
+                    .map_err(|error| Error::InvalidInput(format!("invalid URL: {}", error)))?;
+                url.query_pairs_mut().append_pair("key", key);
+                request.url = url.into();
+            }
+            Credential::Bearer(tokens) => {
+                let token = tokens.token().await?;
+                request
+                    .headers
+                    .push(("Authorization".to_string(), format!("Bearer {}", token)));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// See [`TokenProvider::use_transport`].
+    pub(crate) fn use_transport(&self, transport: &Arc<dyn HttpTransport>) {
+        if let Credential::Bearer(tokens) = self {
+            tokens.use_transport(transport);
+        }
+    }
+
+    /// See [`TokenProvider::invalidate`]. API keys can't be refreshed.
+    pub(crate) async fn invalidate(&self) -> bool {
+        match self {
+            Credential::ApiKey(_) => false,
+            Credential::Bearer(tokens) => tokens.invalidate().await,
+        }
+    }
+}
+
+/// An access token used as is, without refreshing.
+struct StaticToken(String);
+
+#[async_trait]
+impl TokenProvider for StaticToken {
+    async fn token(&self) -> Result<String> {
+        Ok(self.0.clone())
+    }
+}
+
+#[async_trait]
+impl TokenProvider for ServiceAccount {
+    async fn token(&self) -> Result<String> {
+        self.access_token().await
+    }
+
+    async fn invalidate(&self) -> bool {
+        self.cache.invalidate().await;
+        true
+    }
+
+    fn use_transport(&self, transport: &Arc<dyn HttpTransport>) {
+        self.http.set(transport);
+    }
+}
+
+#[async_trait]
+impl TokenProvider for AuthorizedUser {
+    async fn token(&self) -> Result<String> {
+        self.access_token().await
+    }
+
+    async fn invalidate(&self) -> bool {
+        self.cache.invalidate().await;
+        true
+    }
+
+    fn use_transport(&self, transport: &Arc<dyn HttpTransport>) {
+        self.http.set(transport);
+    }
+}
+
+#[async_trait]
+impl TokenProvider for ImpersonatedServiceAccount {
+    async fn token(&self) -> Result<String> {
+        self.access_token().await
+    }
+
+    async fn invalidate(&self) -> bool {
+        self.cache.invalidate().await;
+        true
+    }
+
+    fn use_transport(&self, transport: &Arc<dyn HttpTransport>) {
+        self.http.set(transport);
+        self.source.use_transport(transport);
+    }
+}
+
+#[async_trait]
+impl TokenProvider for MetadataServer {
+    async fn token(&self) -> Result<String> {
+        self.access_token().await
+    }
+
+    async fn invalidate(&self) -> bool {
+        self.cache.invalidate().await;
+        true
+    }
+
+    fn use_transport(&self, transport: &Arc<dyn HttpTransport>) {
+        self.http.set(transport);
+    }
+}
+
+#[cfg(feature = "reqwest")]
+fn from_credentials_file(path: &Path) -> anyhow::Result<Arc<dyn TokenProvider>> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let file: CredentialsFile = serde_json::from_str(&json)
+        .with_context(|| format!("invalid credentials file {}", path.display()))?;
+
+    file.into_token_provider()
+}
+
+/// Application Default Credentials as resolved by the
+/// [gcp_auth](https://docs.rs/gcp_auth) crate.
+#[cfg(feature = "gcp-auth")]
+pub struct GcpAuth(Arc<dyn gcp_auth::TokenProvider>);
+
+#[cfg(feature = "gcp-auth")]
+impl GcpAuth {
+    pub async fn new() -> Result<Self> {
+        Ok(Self(gcp_auth::provider().await.map_err(Error::auth)?))
+    }
+
+    pub fn from_provider(provider: Arc<dyn gcp_auth::TokenProvider>) -> Self {
+        Self(provider)
+    }
+}
+
+#[cfg(feature = "gcp-auth")]
+#[async_trait]
+impl TokenProvider for GcpAuth {
+    async fn token(&self) -> Result<String> {
+        Ok(self
+            .0
+            .token(&[CLOUD_PLATFORM_SCOPE])
+            .await
+            .map_err(Error::auth)?
+            .as_str()
+            .to_string())
+    }
+}
+
+/// Looks up Application Default Credentials: the file named by
+/// `GOOGLE_APPLICATION_CREDENTIALS`, then the user credentials of
+/// `gcloud auth application-default login`, then the metadata server.
+#[cfg(feature = "reqwest")]
+pub(crate) async fn application_default() -> anyhow::Result<Arc<dyn TokenProvider>> {
+    if let Some(path) = std::env::var_os("GOOGLE_APPLICATION_CREDENTIALS") {
+        return from_credentials_file(Path::new(&path));
+    }
+
+    if let Some(path) = gcloud_credentials_path().filter(|path| path.is_file()) {
+        return from_credentials_file(&path);
+    }
+
+    let metadata = MetadataServer::new();
+    anyhow::ensure!(
+        metadata.is_available().await,
+        "could not find Application Default Credentials; set GOOGLE_APPLICATION_CREDENTIALS \
+         or run `gcloud auth application-default login`"
+    );
+
+    Ok(Arc::new(metadata))
+}
+
+/// Where `gcloud auth application-default login` stores its credentials.
+#[cfg(feature = "reqwest")]
+fn gcloud_credentials_path() -> Option<PathBuf> {
+    let config_dir = match std::env::var_os("CLOUDSDK_CONFIG") {
+        Some(dir) => PathBuf::from(dir),
+        None if cfg!(windows) => PathBuf::from(std::env::var_os("APPDATA")?).join("gcloud"),
+        None => PathBuf::from(std::env::var_os("HOME")?)
+            .join(".config")
+            .join("gcloud"),
+    };
+
+    Some(config_dir.join("application_default_credentials.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CachedToken, Credential, HttpRequest, ImpersonatedServiceAccount, MetadataServer,
+        ServiceAccount, TokenCache, CLOUD_PLATFORM_SCOPE,
+    };
+    use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+    use serde_json::Value;
+    use std::time::SystemTime;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn self_signed_jwt() {
+        let service_account = ServiceAccount::from_file("test/service_account.json").unwrap();
+        let jwt = service_account.jwt(SystemTime::now()).unwrap();
+
+        let public_key = std::fs::read("test/service_account_pub.pem").unwrap();
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_audience(&["https://oauth2.googleapis.com/token"]);
+        let decoded = jsonwebtoken::decode::<Value>(
+            &jwt,
+            &DecodingKey::from_rsa_pem(&public_key).unwrap(),
+            &validation,
+        )
+        .unwrap();
+
+        assert_eq!(decoded.header.kid.as_deref(), Some("0123456789abcdef"));
+        assert_eq!(
+            decoded.claims["iss"],
+            "test@gcv-client-test.iam.gserviceaccount.com"
+        );
+        assert_eq!(decoded.claims["scope"], CLOUD_PLATFORM_SCOPE);
+        assert_eq!(
+            decoded.claims["exp"].as_u64().unwrap() - decoded.claims["iat"].as_u64().unwrap(),
+            3600
+        );
+    }
+
+    #[test]
+    fn invalid_key() {
+        assert!(ServiceAccount::from_json("{}").is_err());
+        assert!(ServiceAccount::from_json(
+            r#"{"client_email": "a@b", "private_key": "nope", "token_uri": "https://t"}"#
+        )
+        .is_err());
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[test]
+    fn credentials_file_type() {
+        use super::from_credentials_file;
+        use std::path::Path;
+
+        assert!(from_credentials_file(Path::new("test/service_account.json")).is_ok());
+        assert!(from_credentials_file(Path::new("test/authorized_user.json")).is_ok());
+        assert!(from_credentials_file(Path::new("test/impersonated_service_account.json")).is_ok());
+        assert!(from_credentials_file(Path::new("test/test.png")).is_err());
+    }
+
+    #[test]
+    fn metadata_token_uri() {
+        let metadata = MetadataServer {
+            host: "metadata.google.internal".to_string(),
+            ..MetadataServer::new()
+        };
+        assert_eq!(
+            metadata.token_uri(),
+            "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token"
+        );
+
+        let metadata = metadata
+            .service_account("ocr@project.iam.gserviceaccount.com")
+            .scopes(&[CLOUD_PLATFORM_SCOPE]);
+        assert_eq!(
+            metadata.token_uri(),
+            "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/\
+             ocr@project.iam.gserviceaccount.com/token\
+             ?scopes=https://www.googleapis.com/auth/cloud-platform"
+        );
+    }
+
+    fn token(token: &str, expires_in: u64) -> CachedToken {
+        CachedToken {
+            token: token.to_string(),
+            expires_at: Instant::now() + Duration::from_secs(expires_in),
+        }
+    }
+
+    #[tokio::test]
+    async fn token_cache_refresh() {
+        let cache = TokenCache::default();
+
+        let first = cache
+            .get_or_refresh(|| async { Ok(token("first", 3600)) })
+            .await
+            .unwrap();
+        let cached = cache
+            .get_or_refresh(|| async { Ok(token("second", 3600)) })
+            .await
+            .unwrap();
+        assert_eq!((first.as_str(), cached.as_str()), ("first", "first"));
+
+        cache.invalidate().await;
+        let refreshed = cache
+            .get_or_refresh(|| async { Ok(token("third", 30)) })
+            .await
+            .unwrap();
+        assert_eq!(refreshed, "third");
+
+        // Expires within the margin, so it is refreshed right away.
+        let refreshed = cache
+            .get_or_refresh(|| async { Ok(token("fourth", 3600)) })
+            .await
+            .unwrap();
+        assert_eq!(refreshed, "fourth");
+    }
+
+    #[tokio::test]
+    async fn credential_modes() {
+        let http_request =
+            HttpRequest::post_json("https://vision.googleapis.com/v1/images:annotate", vec![]);
+
+        let mut request = http_request.clone();
+        Credential::ApiKey("KEY".to_string())
+            .authorize(&mut request)
+            .await
+            .unwrap();
+        assert_eq!(
+            request.url,
+            "https://vision.googleapis.com/v1/images:annotate?key=KEY"
+        );
+        assert_eq!(request.headers, http_request.headers);
+
+        let mut request = http_request.clone();
+        Credential::access_token("TOKEN")
+            .authorize(&mut request)
+            .await
+            .unwrap();
+        assert_eq!(request.url, http_request.url);
+        assert_eq!(
+            request.headers.last().unwrap(),
+            &("Authorization".to_string(), "Bearer TOKEN".to_string())
+        );
+    }
+
+    #[test]
+    fn impersonation_delegates() {
+        let source = Credential::access_token("TOKEN");
+        let Credential::Bearer(source) = source else {
+            unreachable!()
+        };
+        let impersonated =
+            ImpersonatedServiceAccount::new(source, "target@p.iam.gserviceaccount.com").delegates(
+                &[
+                    "a@p.iam.gserviceaccount.com",
+                    "projects/-/serviceAccounts/b@p.iam.gserviceaccount.com",
+                ],
+            );
+
+        assert_eq!(
+            impersonated.uri,
+            "https://iamcredentials.googleapis.com/v1/projects/-/serviceAccounts/target@p.iam.gserviceaccount.com:generateAccessToken"
+        );
+        assert_eq!(
+            impersonated.delegates,
+            [
+                "projects/-/serviceAccounts/a@p.iam.gserviceaccount.com",
+                "projects/-/serviceAccounts/b@p.iam.gserviceaccount.com"
+            ]
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! Text annotations that borrow their strings from the JSON they are parsed from, for
+//! pipelines that process many stored responses and would otherwise allocate a `String`
+//! per word and symbol.
+//!
+//! Strings are borrowed whenever possible; those with JSON escapes in a byte buffer are
+//! unescaped into an owned [`Cow`].
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use gcv_client::borrowed::AnnotateImageResponse;
+//!
+//! let json = std::fs::read("photo.json")?;
+//! let response = AnnotateImageResponse::from_slice(&json)?;
+//! for text in &response.text_annotations {
+//!     println!("{}", text.description);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{decode, ApiStatus, BoundingBox, Polygon, Result, TextProperty};
+use serde::Deserialize;
+use serde_json::Value;
+use std::borrow::Cow;
+
+/// The text annotations of one image, see [`crate::AnnotateImageResponse`].
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AnnotateImageResponse<'a> {
+    #[serde(borrow)]
+    pub text_annotations: Vec<TextAnnotation<'a>>,
+    #[serde(borrow)]
+    pub full_text_annotation: Option<FullTextAnnotation<'a>>,
+    pub error: Option<ApiStatus>,
+}
+
+impl<'a> AnnotateImageResponse<'a> {
+    /// Parses the JSON of a response, e.g. one saved from
+    /// [`Response::raw`](crate::Response::raw).
+    pub fn from_slice(json: &'a [u8]) -> Result<Self> {
+        Ok(serde_json::from_slice(json)?)
+    }
+
+    /// Parses `value` without copying its strings.
+    pub fn from_value(value: &'a Value) -> Result<Self> {
+        decode::from_borrowed_value(value, "")
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TextAnnotation<'a> {
+    #[serde(borrow)]
+    pub locale: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    pub description: Cow<'a, str>,
+    #[serde(rename = "boundingPoly", default)]
+    pub bounding_poly: Polygon,
+    pub confidence: Option<f64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct FullTextAnnotation<'a> {
+    #[serde(borrow, default)]
+    pub pages: Vec<Page<'a>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Page<'a> {
+    #[serde(default)]
+    pub property: Option<TextProperty>,
+    #[serde(borrow, default)]
+    pub blocks: Vec<Block<'a>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Block<'a> {
+    #[serde(default)]
+    pub property: Option<TextProperty>,
+    #[serde(rename = "blockType", borrow, default)]
+    pub block_type: Cow<'a, str>,
+    #[serde(rename = "boundingBox", default)]
+    pub bounding_box: BoundingBox,
+    #[serde(default)]
+    pub confidence: f64,
+    #[serde(borrow, default)]
+    pub paragraphs: Vec<Paragraph<'a>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Paragraph<'a> {
+    #[serde(default)]
+    pub property: Option<TextProperty>,
+    #[serde(rename = "boundingBox", default)]
+    pub bounding_box: BoundingBox,
+    #[serde(default)]
+    pub confidence: f64,
+    #[serde(borrow, default)]
+    pub words: Vec<Word<'a>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Word<'a> {
+    #[serde(default)]
+    pub property: Option<TextProperty>,
+    #[serde(rename = "boundingBox", default)]
+    pub bounding_box: BoundingBox,
+    #[serde(default)]
+    pub confidence: f64,
+    #[serde(borrow, default)]
+    pub symbols: Vec<Symbol<'a>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Symbol<'a> {
+    #[serde(default)]
+    pub property: Option<TextProperty>,
+    #[serde(rename = "boundingBox", default)]
+    pub bounding_box: BoundingBox,
+    #[serde(default)]
+    pub confidence: f64,
+    #[serde(borrow, default)]
+    pub text: Cow<'a, str>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AnnotateImageResponse;
+    use crate::Response;
+    use std::borrow::Cow;
+
+    #[test]
+    fn borrowed() {
+        let json = br#"{"textAnnotations": [
+            {"description": "Hello", "boundingPoly": {}},
+            {"description": "\"quoted\"", "boundingPoly": {}}
+        ]}"#;
+
+        let response = AnnotateImageResponse::from_slice(json).unwrap();
+        let texts = &response.text_annotations;
+        assert!(matches!(texts[0].description, Cow::Borrowed("Hello")));
+        assert_eq!(texts[1].description, "\"quoted\"");
+
+        let response = Response::from_json_str(std::str::from_utf8(json).unwrap()).unwrap();
+        let response = AnnotateImageResponse::from_value(response.raw()).unwrap();
+        let texts = &response.text_annotations;
+        assert!(matches!(texts[1].description, Cow::Borrowed("\"quoted\"")));
+    }
+
+    #[test]
+    fn masked_full_text_annotation() {
+        let json = br#"{"fullTextAnnotation": {"pages": [{"blocks": [{"paragraphs": [
+            {"words": [{"symbols": [{"text": "H"}]}]}
+        ]}]}]}}"#;
+
+        let response = AnnotateImageResponse::from_slice(json).unwrap();
+        let block = &response.full_text_annotation.unwrap().pages[0].blocks[0];
+        assert_eq!(block.block_type, "");
+        assert_eq!(block.paragraphs[0].words[0].symbols[0].text, "H");
+    }
+}
@@ -0,0 +1,459 @@
+use crate::retry::RetryCallback;
+use crate::{
+    CircuitBreaker, Client, Credential, Error, HttpTransport, Interceptor, NoRetry, Result,
+    RetryEvent, RetryPolicy, DEFAULT_ENDPOINT,
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Where requests are processed. Images sent to a regional endpoint are processed
+/// and stored only in that region.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Region {
+    #[default]
+    Global,
+    Eu,
+    Us,
+}
+
+impl Region {
+    pub fn endpoint(&self) -> &'static str {
+        match self {
+            Region::Global => DEFAULT_ENDPOINT,
+            Region::Eu => "https://eu-vision.googleapis.com",
+            Region::Us => "https://us-vision.googleapis.com",
+        }
+    }
+}
+
+/// Configures a [`Client`].
+/// ```no_run
+/// # fn main() -> anyhow::Result<()> {
+/// let client = gcv_client::Client::builder()
+///     .credential(gcv_client::Credential::access_token("ya29..."))
+///     .quota_project("my-billing-project")
+///     .region(gcv_client::Region::Eu)
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct ClientBuilder {
+    credential: Option<Credential>,
+    quota_project: Option<String>,
+    endpoint: Option<String>,
+    region: Region,
+    #[cfg(feature = "reqwest")]
+    connect_timeout: Option<Duration>,
+    #[cfg(feature = "reqwest")]
+    timeout: Option<Duration>,
+    transport: Option<Arc<dyn HttpTransport>>,
+    #[cfg(feature = "reqwest")]
+    proxies: Vec<reqwest::Proxy>,
+    headers: Vec<(String, String)>,
+    retry: Option<Arc<dyn RetryPolicy>>,
+    on_throttle: Option<Arc<dyn Fn(Duration) + Send + Sync>>,
+    on_retry: Option<RetryCallback>,
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
+    interceptors: Vec<Arc<dyn Interceptor>>,
+}
+
+impl ClientBuilder {
+    pub fn credential(mut self, credential: Credential) -> Self {
+        self.credential = Some(credential);
+        self
+    }
+
+    /// Bills requests to this project via the `x-goog-user-project` header. Required
+    /// with user credentials when Cloud Vision is enabled in a different project.
+    pub fn quota_project(mut self, project_id: &str) -> Self {
+        self.quota_project = Some(project_id.to_string());
+        self
+    }
+
+    /// Sends requests to another host than [`DEFAULT_ENDPOINT`], e.g. a proxy or a mock
+    /// server. `endpoint` is the base URL without the `/v1/...` path, like
+    /// `http://localhost:8080`.
+    pub fn endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = Some(endpoint.trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Uses the endpoint of `region`. An explicit [`ClientBuilder::endpoint`] takes
+    /// precedence.
+    pub fn region(mut self, region: Region) -> Self {
+        self.region = region;
+        self
+    }
+
+    /// Identifies the application in the `User-Agent` header, e.g. for a gateway that
+    /// routes or audits traffic by it.
+    pub fn user_agent(self, user_agent: &str) -> Self {
+        self.default_header("User-Agent", user_agent)
+    }
+
+    /// Adds a header to every request, e.g. a tracking ID for an internal gateway.
+    /// Unlike timeouts and proxies, headers also apply with a custom transport.
+    pub fn default_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Retries failed calls as `policy` decides, e.g. [`ExponentialBackoff`] for 503s
+    /// and reset connections. Calls are not retried by default.
+    ///
+    /// [`ExponentialBackoff`]: crate::ExponentialBackoff
+    pub fn retry(mut self, policy: impl RetryPolicy + 'static) -> Self {
+        self.retry = Some(Arc::new(policy));
+        self
+    }
+
+    /// Calls `callback` with the delay whenever a response asks to wait with
+    /// `Retry-After` before retrying, e.g. to log throttling by the API.
+    pub fn on_throttle(mut self, callback: impl Fn(Duration) + Send + Sync + 'static) -> Self {
+        self.on_throttle = Some(Arc::new(callback));
+        self
+    }
+
+    /// Calls `callback` before each retry with its number, the cause and the delay, e.g.
+    /// to count retries in metrics or warn about degraded API health.
+    pub fn on_retry(mut self, callback: impl Fn(&RetryEvent<'_>) + Send + Sync + 'static) -> Self {
+        self.on_retry = Some(Arc::new(callback));
+        self
+    }
+
+    /// Fails calls right away while `breaker` is open instead of sending them.
+    pub fn circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Some(Arc::new(breaker));
+        self
+    }
+
+    /// Adds a layer that sees every request before it is sent and its response. Layers
+    /// run in the order they were added.
+    pub fn interceptor(mut self, interceptor: impl Interceptor + 'static) -> Self {
+        self.interceptors.push(Arc::new(interceptor));
+        self
+    }
+
+    /// Time limit for establishing a connection, including the TLS handshake. Also
+    /// applies to fetching access tokens, see [`TokenProvider::use_transport`].
+    ///
+    /// [`TokenProvider::use_transport`]: crate::TokenProvider::use_transport
+    #[cfg(feature = "reqwest")]
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Time limit for each attempt of an API call, from connecting until the response
+    /// body is read, and for each request for an access token. Without one a stalled
+    /// connection blocks the call forever.
+    ///
+    /// With [`retry`](Self::retry), a call may take up to this long per attempt plus the
+    /// delays in between. Bound the whole call with e.g. [`tokio::time::timeout`].
+    #[cfg(feature = "reqwest")]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sends requests through a proxy, including those for access tokens, which disables
+    /// the proxies from environment variables such as `HTTPS_PROXY`. Supports `http://`, `https://` and `socks5://`
+    /// proxies, with credentials via [`reqwest::Proxy::basic_auth`]:
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// let proxy = reqwest::Proxy::https("http://proxy.internal:3128")?.basic_auth("user", "secret");
+    /// let client = gcv_client::Client::builder()
+    ///     .credential(gcv_client::Credential::access_token("ya29..."))
+    ///     .proxy(proxy)
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "reqwest")]
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxies.push(proxy);
+        self
+    }
+
+    /// Sends requests with this client, e.g. one configured with a corporate proxy or
+    /// custom root certificates. Its own settings apply, so it can't be combined with
+    /// the HTTP options of this builder.
+    #[cfg(feature = "reqwest")]
+    pub fn http_client(self, http_client: reqwest::Client) -> Self {
+        self.transport(Arc::new(http_client))
+    }
+
+    /// Sends requests, including those for access tokens, through another HTTP stack
+    /// than reqwest, or a test double. Required without the `reqwest` feature. The HTTP
+    /// options of this builder can't be combined with it either.
+    pub fn transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    fn build_transport(&self) -> Result<Arc<dyn HttpTransport>> {
+        #[cfg(feature = "reqwest")]
+        let has_http_options =
+            self.connect_timeout.is_some() || self.timeout.is_some() || !self.proxies.is_empty();
+        #[cfg(not(feature = "reqwest"))]
+        let has_http_options = false;
+
+        match &self.transport {
+            Some(_) if has_http_options => Err(Error::InvalidInput(
+                "timeouts and proxies can't be set together with a custom http_client or \
+                 transport; configure them on it"
+                    .to_string(),
+            )),
+            Some(transport) => Ok(transport.clone()),
+            #[cfg(feature = "reqwest")]
+            None => Ok(Arc::new(self.build_reqwest()?)),
+            #[cfg(not(feature = "reqwest"))]
+            None => Err(Error::InvalidInput(
+                "a transport is required without the reqwest feature".to_string(),
+            )),
+        }
+    }
+
+    #[cfg(feature = "reqwest")]
+    fn build_reqwest(&self) -> Result<reqwest::Client> {
+        let mut http = reqwest::Client::builder();
+        if let Some(timeout) = self.connect_timeout {
+            http = http.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.timeout {
+            http = http.timeout(timeout);
+        }
+        for proxy in &self.proxies {
+            http = http.proxy(proxy.clone());
+        }
+
+        http.build().map_err(Error::http)
+    }
+
+    pub fn build(self) -> Result<Client> {
+        let http = self.build_transport()?;
+        for (name, value) in &self.headers {
+            if !is_header_name(name) {
+                return Err(Error::InvalidInput(format!(
+                    "invalid header name {:?}",
+                    name
+                )));
+            }
+            if !is_header_value(value) {
+                return Err(Error::InvalidInput(format!(
+                    "invalid value for header {}",
+                    name
+                )));
+            }
+        }
+        let credential = self.credential.ok_or_else(|| {
+            Error::InvalidInput("a credential is required to build a Client".to_string())
+        })?;
+        credential.use_transport(&http);
+
+        Ok(Client {
+            credential,
+            http,
+            endpoint: self
+                .endpoint
+                .unwrap_or_else(|| self.region.endpoint().to_string()),
+            quota_project: self.quota_project,
+            headers: self.headers,
+            retry: self.retry.unwrap_or_else(|| Arc::new(NoRetry)),
+            on_throttle: self.on_throttle,
+            on_retry: self.on_retry,
+            circuit_breaker: self.circuit_breaker,
+            cancellation: None,
+            interceptors: self.interceptors,
+        })
+    }
+}
+
+/// Whether `name` is a token, as HTTP requires of header names.
+fn is_header_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte))
+}
+
+/// Whether `value` has no control characters, which could e.g. start another header.
+fn is_header_value(value: &str) -> bool {
+    value
+        .bytes()
+        .all(|byte| byte == b'\t' || !byte.is_ascii_control())
+}
+
+#[cfg(all(test, feature = "reqwest"))]
+mod tests {
+    use crate::test_server::{image_request, TestServer};
+    use crate::{Client, Credential, Error, Region, ServiceAccount, ServiceAccountKey};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn build() {
+        assert!(Client::builder().build().is_err());
+
+        let client = Client::builder()
+            .credential(Credential::ApiKey("KEY".to_string()))
+            .quota_project("billing")
+            .build()
+            .unwrap();
+        assert_eq!(client.quota_project.as_deref(), Some("billing"));
+        assert_eq!(client.endpoint, "https://vision.googleapis.com");
+    }
+
+    #[test]
+    fn http_client() {
+        let builder = Client::builder()
+            .credential(Credential::ApiKey("KEY".to_string()))
+            .http_client(reqwest::Client::new());
+
+        assert!(builder.timeout(Duration::from_secs(1)).build().is_err());
+    }
+
+    #[test]
+    fn region() {
+        let builder = Client::builder().credential(Credential::ApiKey("KEY".to_string()));
+
+        let client = builder.region(Region::Eu).build().unwrap();
+        assert_eq!(client.endpoint, "https://eu-vision.googleapis.com");
+    }
+
+    #[tokio::test]
+    async fn endpoint() {
+        let server = TestServer::start(vec![(200, r#"{"responses": [{}]}"#.to_string())]).await;
+        let client = Client::builder()
+            .credential(Credential::ApiKey("KEY".to_string()))
+            .quota_project("billing")
+            .endpoint(&format!("{}/", server.url()))
+            .build()
+            .unwrap();
+        let request = image_request();
+
+        client.annotate(&request).await.unwrap();
+
+        let requests = server.requests().await;
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].starts_with("POST /v1/images:annotate?key=KEY HTTP/1.1\r\n"));
+        assert!(requests[0]
+            .to_lowercase()
+            .contains("x-goog-user-project: billing\r\n"));
+    }
+
+    #[tokio::test]
+    async fn default_headers() {
+        let server = TestServer::start(vec![(200, r#"{"responses": [{}]}"#.to_string())]).await;
+        let client = server
+            .client_builder()
+            .user_agent("ocr-batch/1.2")
+            .default_header("x-tracking-id", "abc123")
+            .build()
+            .unwrap();
+        let request = image_request();
+
+        client.annotate(&request).await.unwrap();
+
+        let request = server.requests().await[0].to_lowercase();
+        assert!(request.contains("user-agent: ocr-batch/1.2\r\n"));
+        assert!(request.contains("x-tracking-id: abc123\r\n"));
+
+        let builder = Client::builder().credential(Credential::ApiKey("KEY".to_string()));
+        assert!(builder.default_header("x-bad", "a\nb").build().is_err());
+    }
+
+    #[tokio::test]
+    async fn proxy() {
+        let proxy = TestServer::start(vec![(200, r#"{"responses": [{}]}"#.to_string())]).await;
+        let client = Client::builder()
+            .credential(Credential::ApiKey("KEY".to_string()))
+            .endpoint("http://vision.invalid")
+            .proxy(
+                reqwest::Proxy::http(proxy.url())
+                    .unwrap()
+                    .basic_auth("user", "secret"),
+            )
+            .build()
+            .unwrap();
+        let request = image_request();
+
+        client.annotate(&request).await.unwrap();
+
+        let requests = proxy.requests().await;
+        assert!(requests[0].starts_with("POST http://vision.invalid/v1/images:annotate?key=KEY "));
+        // "user:secret" in base64
+        assert!(requests[0].contains("Basic dXNlcjpzZWNyZXQ="));
+    }
+
+    #[tokio::test]
+    async fn timeout() {
+        let server = TestServer::start_silent().await;
+        let client = server
+            .client_builder()
+            .timeout(Duration::from_millis(100))
+            .build()
+            .unwrap();
+        let request = image_request();
+
+        assert!(client.annotate(&request).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn token_timeout() {
+        let token_server = TestServer::start_silent().await;
+        let json = std::fs::read_to_string("test/service_account.json").unwrap();
+        let key = ServiceAccountKey {
+            token_uri: token_server.url(),
+            ..serde_json::from_str(&json).unwrap()
+        };
+        let client = Client::builder()
+            .credential(Credential::Bearer(Arc::new(
+                ServiceAccount::new(key).unwrap(),
+            )))
+            .endpoint("http://vision.invalid")
+            .timeout(Duration::from_millis(100))
+            .build()
+            .unwrap();
+        let request = image_request();
+
+        let result = tokio::time::timeout(Duration::from_secs(5), client.annotate(&request))
+            .await
+            .expect("the token request should time out");
+        assert!(matches!(result.err().unwrap(), Error::Auth(_)));
+    }
+
+    #[tokio::test]
+    async fn token_proxy() {
+        let proxy = TestServer::start(vec![
+            (
+                200,
+                r#"{"access_token": "ya29.a", "expires_in": 3600}"#.to_string(),
+            ),
+            (200, r#"{"responses": [{}]}"#.to_string()),
+        ])
+        .await;
+        let json = std::fs::read_to_string("test/service_account.json").unwrap();
+        let key = ServiceAccountKey {
+            token_uri: "http://oauth.invalid/token".to_string(),
+            ..serde_json::from_str(&json).unwrap()
+        };
+        let client = Client::builder()
+            .credential(Credential::Bearer(Arc::new(
+                ServiceAccount::new(key).unwrap(),
+            )))
+            .endpoint("http://vision.invalid")
+            .proxy(reqwest::Proxy::http(proxy.url()).unwrap())
+            .build()
+            .unwrap();
+        let request = image_request();
+
+        client.annotate(&request).await.unwrap();
+
+        let requests = proxy.requests().await;
+        assert!(requests[0].starts_with("POST http://oauth.invalid/token "));
+        assert!(requests[1].starts_with("POST http://vision.invalid/v1/images:annotate "));
+        assert!(requests[1].contains("Bearer ya29.a"));
+    }
+}
@@ -0,0 +1,197 @@
+use crate::{Error, Outcome, Result};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls go through.
+    Closed,
+    /// Calls fail right away until the cool-down has passed.
+    Open,
+    /// One trial call goes through; it closes the circuit on success and opens it again
+    /// on failure.
+    HalfOpen,
+}
+
+/// Stops calling the API while it keeps failing, so that callers fail fast instead of
+/// waiting for timeouts during an outage. Shared by the clones of a
+/// [`Client`](crate::Client).
+///
+/// Transient failures (see [`Outcome::is_transient`]) count towards tripping it, every
+/// other response resets the count.
+/// ```no_run
+/// # fn main() -> anyhow::Result<()> {
+/// use gcv_client::CircuitBreaker;
+/// use std::time::Duration;
+///
+/// let breaker = CircuitBreaker::new(5, Duration::from_secs(30))
+///     .on_state_change(|state| eprintln!("Cloud Vision circuit is now {:?}", state));
+/// let client = gcv_client::Client::builder()
+///     .credential(gcv_client::Credential::access_token("ya29..."))
+///     .circuit_breaker(breaker)
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cool_down: Duration,
+    on_state_change: Option<Arc<dyn Fn(CircuitState) + Send + Sync>>,
+    state: Mutex<State>,
+}
+
+struct State {
+    circuit: CircuitState,
+    consecutive_failures: u32,
+    /// When `circuit` was entered.
+    since: Instant,
+}
+
+impl CircuitBreaker {
+    /// Opens after `failure_threshold` consecutive failures and stays open for `cool_down`.
+    pub fn new(failure_threshold: u32, cool_down: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cool_down,
+            on_state_change: None,
+            state: Mutex::new(State {
+                circuit: CircuitState::Closed,
+                consecutive_failures: 0,
+                since: Instant::now(),
+            }),
+        }
+    }
+
+    /// Calls `callback` with the new state on every transition.
+    pub fn on_state_change(
+        mut self,
+        callback: impl Fn(CircuitState) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_state_change = Some(Arc::new(callback));
+        self
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.state.lock().unwrap().circuit
+    }
+
+    /// Fails if no call may be made now.
+    pub(crate) fn acquire(&self) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        match state.circuit {
+            CircuitState::Closed => Ok(()),
+            // A trial whose call was dropped never reports back, so allow another one
+            // after a cool-down as well.
+            CircuitState::Open | CircuitState::HalfOpen
+                if state.since.elapsed() >= self.cool_down =>
+            {
+                let changed = state.transition(CircuitState::HalfOpen);
+                drop(state);
+                self.notify(changed);
+                Ok(())
+            }
+            CircuitState::Open | CircuitState::HalfOpen => Err(Error::CircuitOpen),
+        }
+    }
+
+    /// Records the result of a call allowed by [`CircuitBreaker::acquire`].
+    pub(crate) fn record(&self, outcome: Option<Outcome<'_>>) {
+        let failed = outcome.is_some_and(|outcome| outcome.is_transient());
+        let mut state = self.state.lock().unwrap();
+        let changed = if !failed {
+            state.consecutive_failures = 0;
+            state.transition(CircuitState::Closed)
+        } else {
+            state.consecutive_failures += 1;
+            if state.circuit == CircuitState::HalfOpen
+                || state.consecutive_failures >= self.failure_threshold
+            {
+                state.transition(CircuitState::Open)
+            } else {
+                None
+            }
+        };
+        drop(state);
+        self.notify(changed);
+    }
+
+    fn notify(&self, changed: Option<CircuitState>) {
+        if let (Some(state), Some(callback)) = (changed, &self.on_state_change) {
+            callback(state);
+        }
+    }
+}
+
+impl State {
+    /// Enters `circuit`, returning it if that is a change.
+    fn transition(&mut self, circuit: CircuitState) -> Option<CircuitState> {
+        if self.circuit == circuit {
+            return None;
+        }
+        self.circuit = circuit;
+        self.since = Instant::now();
+        Some(circuit)
+    }
+}
+
+#[cfg(all(test, feature = "reqwest"))]
+mod tests {
+    use super::{CircuitBreaker, CircuitState};
+    use crate::test_server::{image_request, TestServer};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn trips_and_recovers() {
+        let server = TestServer::start(vec![
+            (503, r#"{"error": {"code": 503}}"#.to_string()),
+            (503, r#"{"error": {"code": 503}}"#.to_string()),
+            (200, r#"{"responses": [{}]}"#.to_string()),
+        ])
+        .await;
+        let states = Arc::new(Mutex::new(vec![]));
+        let observed = states.clone();
+        let breaker = CircuitBreaker::new(2, Duration::from_millis(200))
+            .on_state_change(move |state| observed.lock().unwrap().push(state));
+        let client = server
+            .client_builder()
+            .circuit_breaker(breaker)
+            .build()
+            .unwrap();
+        let request = image_request();
+
+        assert!(client.annotate(&request).await.is_err());
+        assert!(client.annotate(&request).await.is_err());
+        // fails fast without calling the API
+        let error = client.annotate(&request).await.err().unwrap();
+        assert!(matches!(error, crate::Error::CircuitOpen));
+        assert_eq!(server.requests().await.len(), 2);
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        client.annotate(&request).await.unwrap();
+        assert_eq!(server.requests().await.len(), 3);
+        assert_eq!(
+            *states.lock().unwrap(),
+            [
+                CircuitState::Open,
+                CircuitState::HalfOpen,
+                CircuitState::Closed
+            ]
+        );
+    }
+
+    #[test]
+    fn failed_trial_reopens() {
+        let breaker = CircuitBreaker::new(1, Duration::ZERO);
+        let error = crate::Error::http(std::io::Error::from(std::io::ErrorKind::ConnectionRefused));
+
+        breaker.acquire().unwrap();
+        breaker.record(Some(crate::Outcome::Error(&error)));
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        breaker.acquire().unwrap();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        breaker.record(Some(crate::Outcome::Error(&error)));
+        assert_eq!(breaker.state(), CircuitState::Open);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::{AnnotateImageRequest, Client, Error, Response, Result};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Maximum number of calls [`Client::annotate_many`] has in flight at once.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Concurrency(pub usize);
+
+impl Default for Concurrency {
+    fn default() -> Self {
+        Self(8)
+    }
+}
+
+impl Client {
+    /// Annotates each request in its own call, at most `concurrency` at a time, and
+    /// returns the results in the same order as `requests`. A failed call doesn't stop
+    /// the others; its error is an [`Error::Input`] naming the position and tag of the
+    /// request. Dropping the future aborts the calls still running.
+    /// ```no_run
+    /// # async fn f(client: gcv_client::Client, paths: Vec<std::path::PathBuf>) -> anyhow::Result<()> {
+    /// use gcv_client::{AnnotateRequestBuilder, Concurrency, Feature, ImageGCV};
+    ///
+    /// let mut requests = vec![];
+    /// for path in &paths {
+    ///     let request = AnnotateRequestBuilder::new(&ImageGCV::from_path(path)?)
+    ///         .feature(Feature::DocumentTextDetection)
+    ///         .tag(path.display().to_string())
+    ///         .build();
+    ///     requests.push(request);
+    /// }
+    /// for result in client.annotate_many(requests, Concurrency(8)).await {
+    ///     match result {
+    ///         Ok(response) => println!("{:?}", response.text_annotations()),
+    ///         // e.g. "image 3 (scans/0003.png): API error: ..."
+    ///         Err(error) => eprintln!("{}", error),
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn annotate_many(
+        &self,
+        requests: impl IntoIterator<Item = AnnotateImageRequest>,
+        concurrency: Concurrency,
+    ) -> Vec<Result<Response>> {
+        let semaphore = Arc::new(Semaphore::new(concurrency.0.max(1)));
+        let mut calls = JoinSet::new();
+        let mut len = 0;
+        let mut tags = vec![];
+        for (i, request) in requests.into_iter().enumerate() {
+            let client = self.clone();
+            let semaphore = semaphore.clone();
+            tags.push(request.tag.clone());
+            calls.spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                (i, client.annotate(&request).await)
+            });
+            len += 1;
+        }
+
+        let mut results: Vec<_> = (0..len).map(|_| None).collect();
+        while let Some(joined) = calls.join_next().await {
+            match joined {
+                Ok((i, result)) => results[i] = Some(result),
+                Err(error) if error.is_panic() => std::panic::resume_unwind(error.into_panic()),
+                Err(_) => {}
+            }
+        }
+
+        results
+            .into_iter()
+            .zip(tags)
+            .enumerate()
+            .map(|(index, (result, tag))| {
+                result
+                    .unwrap_or(Err(Error::Cancelled))
+                    .map_err(|error| Error::Input {
+                        index,
+                        tag,
+                        error: Box::new(error),
+                    })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Concurrency;
+    use crate::{
+        AnnotateRequestBuilder, Client, Credential, Error, HttpRequest, HttpResponse,
+        HttpTransport, ImageSource,
+    };
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Fails the request for `bad.png` and records the most calls it had in flight.
+    #[derive(Default)]
+    struct Slow {
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl HttpTransport for Slow {
+        async fn send(&self, request: HttpRequest) -> crate::Result<HttpResponse> {
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            let body = String::from_utf8(request.body).unwrap();
+            let (status, body) = if body.contains("bad.png") {
+                (400, r#"{"error": {"code": 400}}"#)
+            } else {
+                (200, r#"{"responses": [{}]}"#)
+            };
+            Ok(HttpResponse {
+                status,
+                headers: vec![],
+                body: body.as_bytes().to_vec(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn annotate_many() {
+        let transport = Arc::new(Slow::default());
+        let client = Client::with_transport(transport.clone(), Credential::access_token("TOKEN"));
+        let requests = (0..10).map(|i| {
+            let name = if i == 3 { "bad" } else { "good" };
+            AnnotateRequestBuilder::new(ImageSource::ImageUri(format!(
+                "https://example.com/{}.png",
+                name
+            )))
+            .tag(i.to_string())
+            .build()
+        });
+
+        let results = client.annotate_many(requests, Concurrency(4)).await;
+
+        let failed: Vec<_> = results.iter().map(|result| result.is_err()).collect();
+        assert_eq!(
+            failed,
+            [false, false, false, true, false, false, false, false, false, false]
+        );
+        match results[3].as_ref().err().unwrap() {
+            error @ Error::Input { index, tag, .. } => {
+                assert_eq!((*index, tag.as_deref()), (3, Some("3")));
+                assert!(matches!(error.inner(), Error::Api { .. }));
+                assert!(error.to_string().starts_with("image 3 (3): API error: 400"));
+            }
+            error => panic!("{}", error),
+        }
+        assert_eq!(transport.max_in_flight.load(Ordering::SeqCst), 4);
+    }
+}
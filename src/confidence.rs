@@ -0,0 +1,96 @@
+use crate::{FullTextAnnotation, Page, Symbol, Word};
+
+impl FullTextAnnotation {
+    /// The words whose confidence is below `min_confidence`, e.g. to flag them for review.
+    pub fn low_confidence_words(&self, min_confidence: f64) -> impl Iterator<Item = &Word> + '_ {
+        self.words()
+            .filter(move |word| word.confidence < min_confidence)
+    }
+
+    /// Drops the words whose confidence is below `min_confidence`, along with the
+    /// paragraphs and blocks left without words, so noisy regions don't end up in the
+    /// text. Words without a confidence count as 0.
+    pub fn filter_confidence(&self, min_confidence: f64) -> FullTextAnnotation {
+        self.map_words(|mut words| {
+            words.retain(|word| word.confidence >= min_confidence);
+            words
+        })
+    }
+
+    /// Replaces each word whose confidence is below `min_confidence` with `placeholder`,
+    /// e.g. `"�"`, keeping its box and the break after it.
+    pub fn replace_low_confidence(
+        &self,
+        min_confidence: f64,
+        placeholder: &str,
+    ) -> FullTextAnnotation {
+        self.map_words(|words| {
+            words
+                .into_iter()
+                .map(|mut word| {
+                    if word.confidence < min_confidence {
+                        let property = word.symbols.last().and_then(|s| s.property.clone());
+                        word.symbols = vec![Symbol {
+                            property,
+                            bounding_box: word.bounding_box.clone(),
+                            confidence: word.confidence,
+                            text: placeholder.to_string(),
+                        }];
+                    }
+                    word
+                })
+                .collect()
+        })
+    }
+
+    /// Rebuilds the hierarchy with the words of each paragraph changed by `f`, dropping
+    /// what is left empty, and the text put together again.
+    fn map_words(&self, f: impl Fn(Vec<Word>) -> Vec<Word>) -> FullTextAnnotation {
+        let mut full_text = self.clone();
+        for page in &mut full_text.pages {
+            for block in &mut page.blocks {
+                for paragraph in &mut block.paragraphs {
+                    paragraph.words = f(std::mem::take(&mut paragraph.words));
+                }
+                block
+                    .paragraphs
+                    .retain(|paragraph| !paragraph.words.is_empty());
+            }
+            page.blocks.retain(|block| !block.paragraphs.is_empty());
+        }
+        full_text.text = full_text.pages.iter().map(Page::text).collect();
+        full_text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_fixtures::full_text_of_blocks;
+    use crate::Rect;
+    use serde_json::{json, Value};
+
+    fn word(text: &str, confidence: f64) -> Value {
+        let mut word = crate::test_fixtures::word(text, Rect::default());
+        word["confidence"] = json!(confidence);
+        word["symbols"][0]["property"] = json!({ "detectedBreak": { "type": "SPACE" } });
+        word
+    }
+
+    #[test]
+    fn filter_confidence() {
+        let mut full_text = full_text_of_blocks(vec![
+            vec![vec![word("a", 0.9), word("#", 0.3), word("b", 0.95)]],
+            vec![vec![word("~", 0.1)]],
+        ]);
+        full_text.text = "a # b ".to_string();
+
+        assert_eq!(full_text.low_confidence_words(0.8).count(), 2);
+
+        let filtered = full_text.filter_confidence(0.8);
+        assert_eq!(filtered.text, "a b ");
+        assert_eq!(filtered.pages[0].blocks.len(), 1);
+
+        let replaced = full_text.replace_low_confidence(0.8, "?");
+        assert_eq!(replaced.text, "a ? b ? ");
+    }
+}
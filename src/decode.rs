@@ -0,0 +1,177 @@
+//! Deserializes annotations with errors that point at the part of the response that
+//! doesn't match the model of this crate.
+
+use crate::{Error, Result};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use serde_path_to_error::Segment;
+
+/// Characters of the offending value kept in [`SchemaMismatch::snippet`].
+const MAX_SNIPPET: usize = 200;
+
+/// A response that doesn't match the model of this crate, e.g. because the API changed.
+/// The source of an [`Error::Decode`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{message} at {pointer}: {snippet}")]
+pub struct SchemaMismatch {
+    /// JSON pointer into the response of the image, e.g. `/textAnnotations/3/description`.
+    pub pointer: String,
+    /// What was expected there, e.g. `a string`, if known.
+    pub expected: Option<String>,
+    /// What went wrong, e.g. `invalid type: integer `5`, expected a string`.
+    pub message: String,
+    /// The start of the offending value.
+    pub snippet: String,
+}
+
+impl SchemaMismatch {
+    /// `path` leads from `value`, found at `pointer` in the response, to where `error`
+    /// happened.
+    fn new(value: &Value, pointer: &str, path: &str, error: serde_json::Error) -> Self {
+        let message = error.to_string();
+        let expected = message
+            .split_once(", expected ")
+            .map(|(_, expected)| expected.to_string());
+
+        let mut snippet = value.pointer(path).unwrap_or(value).to_string();
+        if let Some((end, _)) = snippet.char_indices().nth(MAX_SNIPPET) {
+            snippet.truncate(end);
+            snippet.push('…');
+        }
+
+        Self {
+            pointer: format!("{}{}", pointer, path),
+            expected,
+            message,
+            snippet,
+        }
+    }
+}
+
+/// Deserializes `value`, found at `pointer` in the response, without copying it.
+pub(crate) fn from_value<T: DeserializeOwned>(value: &Value, pointer: &str) -> Result<T> {
+    from_borrowed_value(value, pointer)
+}
+
+/// Deserializes `value`, found at `pointer` in the response, borrowing its strings.
+pub(crate) fn from_borrowed_value<'a, T: Deserialize<'a>>(
+    value: &'a Value,
+    pointer: &str,
+) -> Result<T> {
+    serde_path_to_error::deserialize(value).map_err(|error| {
+        let path = json_pointer(error.path());
+        Error::decode(SchemaMismatch::new(
+            value,
+            pointer,
+            &path,
+            error.into_inner(),
+        ))
+    })
+}
+
+/// Parses the body of a call.
+#[cfg(not(feature = "simd-json"))]
+pub(crate) fn parse_body(body: &mut [u8]) -> Result<Value> {
+    Ok(serde_json::from_slice(body)?)
+}
+
+/// Parses the body of a call with simd-json, which works on the body in place.
+#[cfg(feature = "simd-json")]
+pub(crate) fn parse_body(body: &mut [u8]) -> Result<Value> {
+    simd_json::serde::from_slice(body).map_err(Error::decode)
+}
+
+/// The path as a JSON pointer, e.g. `/boundingPoly/vertices/1/x`.
+fn json_pointer(path: &serde_path_to_error::Path) -> String {
+    let mut pointer = String::new();
+    for segment in path.iter() {
+        match segment {
+            Segment::Seq { index } => pointer.push_str(&format!("/{}", index)),
+            Segment::Map { key } | Segment::Enum { variant: key } => {
+                pointer.push_str(&format!("/{}", key.replace('~', "~0").replace('/', "~1")))
+            }
+            Segment::Unknown => {}
+        }
+    }
+    pointer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_value, SchemaMismatch};
+    use crate::{AnnotateImageResponse, Error, TextAnnotation};
+    use serde_json::{json, Value};
+
+    fn mismatch<T: serde::de::DeserializeOwned + std::fmt::Debug>(
+        value: &Value,
+        pointer: &str,
+    ) -> SchemaMismatch {
+        let error = from_value::<T>(value, pointer).unwrap_err();
+        let Error::Decode(source) = error else {
+            panic!("expected a decode error, got {:?}", error);
+        };
+        *source.downcast::<SchemaMismatch>().unwrap()
+    }
+
+    #[test]
+    fn parse_body() {
+        let mut body = br#"{"textAnnotations": [{"description": "Hello \u00e9"}]}"#.to_vec();
+        let value = super::parse_body(&mut body).unwrap();
+        assert_eq!(
+            value,
+            json!({ "textAnnotations": [{ "description": "Hello é" }] })
+        );
+
+        assert!(matches!(
+            super::parse_body(&mut b"{".to_vec()),
+            Err(Error::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn schema_mismatch() {
+        let value = json!({
+            "description": "Hello",
+            "boundingPoly": { "vertices": [{ "x": 1, "y": 2 }, { "x": "3", "y": 4 }] }
+        });
+
+        let mismatch = mismatch::<TextAnnotation>(&value, "/textAnnotations/2");
+        assert_eq!(
+            mismatch.pointer,
+            "/textAnnotations/2/boundingPoly/vertices/1/x"
+        );
+        assert_eq!(mismatch.expected.as_deref(), Some("i64"));
+        assert_eq!(mismatch.snippet, r#""3""#);
+    }
+
+    #[test]
+    fn later_element() {
+        // A string is fine as the description of the first annotation, but not as a vertex
+        // of the second.
+        let value = json!({ "textAnnotations": [
+            { "description": "3", "boundingPoly": { "vertices": [{ "x": 1, "y": 2 }] } },
+            { "description": "Hello", "boundingPoly": { "vertices": [{ "x": "3", "y": 2 }] } }
+        ] });
+
+        let mismatch = mismatch::<AnnotateImageResponse>(&value, "");
+        assert_eq!(
+            mismatch.pointer,
+            "/textAnnotations/1/boundingPoly/vertices/0/x"
+        );
+        assert_eq!(mismatch.snippet, r#""3""#);
+    }
+
+    #[test]
+    fn missing_field() {
+        let value = json!({ "textAnnotations": [
+            { "description": "Hello" },
+            { "boundingPoly": { "vertices": [] } }
+        ] });
+
+        let mismatch = mismatch::<AnnotateImageResponse>(&value, "/responses/0");
+        assert_eq!(mismatch.pointer, "/responses/0/textAnnotations/1");
+        assert_eq!(mismatch.message, "missing field `description`");
+        assert_eq!(mismatch.snippet, r#"{"boundingPoly":{"vertices":[]}}"#);
+    }
+}
@@ -0,0 +1,88 @@
+use crate::{Rect, TextAnnotation};
+use std::collections::HashMap;
+
+/// Merges the words found more than once, e.g. by overlapping tiles or repeated requests
+/// for screenshots stitched together: annotations with the same text whose boxes overlap
+/// with an intersection over union of at least `min_iou` are kept once, as the one with
+/// the largest box, which is the least likely to be cut off. Order is kept otherwise.
+///
+/// `0.5` is a reasonable `min_iou` for annotations in the same coordinates, see
+/// [`Response::map_geometry`](crate::Response::map_geometry) to get them there.
+pub fn dedup_text_annotations(
+    annotations: Vec<TextAnnotation>,
+    min_iou: f64,
+) -> Vec<TextAnnotation> {
+    let mut kept: Vec<(Rect, TextAnnotation)> = vec![];
+    // indices into `kept` by text, as only words with the same text can be duplicates
+    let mut by_text: HashMap<String, Vec<usize>> = HashMap::new();
+
+    for annotation in annotations {
+        let rect = annotation.bounding_poly.rect();
+        let same_text = by_text.entry(annotation.description.clone()).or_default();
+        let duplicate = same_text
+            .iter()
+            .copied()
+            .find(|&i| kept[i].0.iou(&rect) >= min_iou);
+
+        match duplicate {
+            Some(i) => {
+                if rect.area() > kept[i].0.area() {
+                    kept[i] = (rect, annotation);
+                }
+            }
+            None => {
+                same_text.push(kept.len());
+                kept.push((rect, annotation));
+            }
+        }
+    }
+
+    kept.into_iter().map(|(_, annotation)| annotation).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::dedup_text_annotations;
+    use crate::{Point, Polygon, TextAnnotation};
+
+    fn word(text: &str, left: i64, right: i64) -> TextAnnotation {
+        TextAnnotation {
+            locale: None,
+            description: text.to_string(),
+            bounding_poly: Polygon {
+                vertices: vec![
+                    Point { x: left, y: 10 },
+                    Point { x: right, y: 10 },
+                    Point { x: right, y: 20 },
+                    Point { x: left, y: 20 },
+                ],
+                normalized_vertices: vec![],
+            },
+            confidence: None,
+        }
+    }
+
+    #[test]
+    fn merges_duplicates() {
+        // Two screenshots sharing "World" and "again", shifted by a pixel.
+        let first = vec![
+            word("Hello", 0, 50),
+            word("World", 60, 110),
+            word("again", 120, 168),
+        ];
+        let second = vec![
+            word("World", 61, 111),
+            word("again", 121, 170),
+            word("!", 175, 180),
+        ];
+        // The same text elsewhere is a word of its own.
+        let elsewhere = vec![word("Hello", 300, 350)];
+
+        let merged = dedup_text_annotations([first, second, elsewhere].concat(), 0.5);
+
+        let texts: Vec<_> = merged.iter().map(|a| a.description.as_str()).collect();
+        assert_eq!(texts, ["Hello", "World", "again", "!", "Hello"]);
+        // The larger of the two boxes of "again" wins.
+        assert_eq!(merged[2].bounding_poly.vertices[1].x, 170);
+    }
+}
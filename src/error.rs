@@ -0,0 +1,473 @@
+use crate::{HttpResponse, Outcome};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+
+/// Bytes of a failed response's body kept in [`HttpFailure::body`].
+const MAX_FAILURE_BODY: usize = 1024;
+
+/// Any error, as the source of an [`Error`].
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Errors of this crate, classified so that callers can decide whether to retry, report
+/// to the user or skip the image.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// The HTTP call failed without a response, e.g. the connection was refused.
+    #[error("HTTP request failed: {0}")]
+    Http(#[source] BoxError),
+    /// The API answered with an error.
+    #[error("API error: {error}")]
+    Api {
+        error: GoogleApiError,
+        response: Box<HttpFailure>,
+    },
+    /// The API answered with `RESOURCE_EXHAUSTED` or HTTP 429: slow down rather than fix
+    /// the request. `error` is `None` if the response had no error object of the API.
+    #[error("quota exceeded: {}", match error {
+        Some(error) => error.to_string(),
+        None => format!("HTTP {}", response.status),
+    })]
+    QuotaExceeded {
+        quota: Box<QuotaViolation>,
+        error: Option<GoogleApiError>,
+        response: Box<HttpFailure>,
+    },
+    /// The API could not process an image, though the call as a whole succeeded.
+    #[error("image failed: {0}")]
+    Image(ApiStatus),
+    /// The call failed with an HTTP status but without an error object of the API, e.g.
+    /// from a proxy.
+    #[error("HTTP {}: {}", .0.status, .0.body)]
+    Status(Box<HttpFailure>),
+    /// The response of the API was not as expected.
+    #[error("invalid response: {0}")]
+    Decode(#[source] BoxError),
+    /// An image could not be read, decoded or encoded, or a response could not be saved.
+    #[error("image error: {0}")]
+    Encode(#[source] BoxError),
+    /// No access token could be obtained.
+    #[error("authentication failed: {0}")]
+    Auth(#[source] BoxError),
+    /// The call took longer than its timeout or deadline.
+    #[error("request timed out")]
+    Timeout,
+    /// The call was aborted with a [`CancellationToken`](crate::CancellationToken).
+    #[error("request cancelled")]
+    Cancelled,
+    /// The [`CircuitBreaker`](crate::CircuitBreaker) is open.
+    #[error("circuit breaker is open after repeated failures of the API")]
+    CircuitOpen,
+    /// An argument or the configuration is invalid, e.g. an image is too large.
+    #[error("{0}")]
+    InvalidInput(String),
+    /// The call for one input of a bulk operation failed.
+    #[error("image {index}{}: {error}", tag.as_ref().map(|tag| format!(" ({})", tag)).unwrap_or_default())]
+    Input {
+        /// Position of the input, counting from 0.
+        index: usize,
+        /// The [`AnnotateImageRequest::tag`](crate::AnnotateImageRequest::tag) of the input.
+        tag: Option<String>,
+        #[source]
+        error: Box<Error>,
+    },
+}
+
+impl Error {
+    pub fn http(error: impl Into<BoxError>) -> Self {
+        Error::Http(error.into())
+    }
+
+    pub fn decode(error: impl Into<BoxError>) -> Self {
+        Error::Decode(error.into())
+    }
+
+    pub fn encode(error: impl Into<BoxError>) -> Self {
+        Error::Encode(error.into())
+    }
+
+    pub fn auth(error: impl Into<BoxError>) -> Self {
+        Error::Auth(error.into())
+    }
+
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            Error::Http(_) | Error::Timeout => ErrorClass::Transport,
+            Error::Api { .. }
+            | Error::QuotaExceeded { .. }
+            | Error::Image(_)
+            | Error::Status(_) => ErrorClass::Api,
+            Error::Decode(_) => ErrorClass::Parse,
+            Error::Input { error, .. } => error.class(),
+            Error::Encode(_)
+            | Error::Auth(_)
+            | Error::Cancelled
+            | Error::CircuitOpen
+            | Error::InvalidInput(_) => ErrorClass::Other,
+        }
+    }
+
+    /// Whether the same call may succeed when tried again: timeouts, connections that
+    /// were refused or reset, exceeded quotas, and HTTP 500, 502 and 503 or the matching
+    /// codes of a failed image.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Http(source) => is_connection_error(&**source),
+            Error::Timeout | Error::QuotaExceeded { .. } => true,
+            Error::Api { response, .. } | Error::Status(response) => {
+                is_transient_status(response.status)
+            }
+            // RESOURCE_EXHAUSTED, INTERNAL and UNAVAILABLE
+            Error::Image(status) => matches!(status.code, 8 | 13 | 14),
+            Error::Input { error, .. } => error.is_retryable(),
+            _ => false,
+        }
+    }
+
+    /// The error itself, without the input an [`Error::Input`] attributes it to.
+    pub fn inner(&self) -> &Error {
+        match self {
+            Error::Input { error, .. } => error.inner(),
+            error => error,
+        }
+    }
+}
+
+/// The broad kind of an [`Error`], for handling errors without matching every variant.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorClass {
+    /// The API could not be reached or did not answer in time; usually worth retrying.
+    Transport,
+    /// The API answered with an error; worth retrying only for some statuses, see
+    /// [`Error::is_retryable`].
+    Api,
+    /// The response could not be parsed. Retrying won't help; report it instead.
+    Parse,
+    /// Invalid input, missing credentials, cancellation and the like.
+    Other,
+}
+
+/// Whether a response with `status` may succeed when the call is tried again.
+pub(crate) fn is_transient_status(status: u16) -> bool {
+    matches!(status, 429 | 500 | 502 | 503)
+}
+
+/// Whether `error` or one of its causes is a connection that was refused or dropped.
+fn is_connection_error(error: &(dyn std::error::Error + 'static)) -> bool {
+    let mut causes = std::iter::successors(Some(error), |cause| cause.source());
+
+    causes.any(|cause| {
+        #[cfg(feature = "reqwest")]
+        if let Some(error) = cause.downcast_ref::<reqwest::Error>() {
+            return error.is_connect();
+        }
+        cause.downcast_ref::<std::io::Error>().is_some_and(|error| {
+            matches!(
+                error.kind(),
+                std::io::ErrorKind::ConnectionRefused
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::BrokenPipe
+                    | std::io::ErrorKind::UnexpectedEof
+            )
+        })
+    })
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error::decode(error)
+    }
+}
+
+impl From<image::ImageError> for Error {
+    fn from(error: image::ImageError) -> Self {
+        Error::encode(error)
+    }
+}
+
+/// What a failed response looked like, for debugging e.g. a 403 versus a 429.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpFailure {
+    pub status: u16,
+    /// The headers that help to debug, such as `x-debug-tracking-id`, `Retry-After` and
+    /// the `x-goog-*` ones.
+    pub headers: Vec<(String, String)>,
+    /// The start of the body, cut after about 1 KB.
+    pub body: String,
+}
+
+impl HttpFailure {
+    pub(crate) fn new(response: &HttpResponse) -> Self {
+        let headers = response
+            .headers
+            .iter()
+            .filter(|(name, _)| {
+                let name = name.to_ascii_lowercase();
+                name.starts_with("x-goog-")
+                    || name.starts_with("x-debug-")
+                    || matches!(
+                        name.as_str(),
+                        "content-type" | "retry-after" | "www-authenticate" | "x-request-id"
+                    )
+            })
+            .cloned()
+            .collect();
+
+        let mut body = String::from_utf8_lossy(&response.body).into_owned();
+        if body.len() > MAX_FAILURE_BODY {
+            let mut end = MAX_FAILURE_BODY;
+            while !body.is_char_boundary(end) {
+                end -= 1;
+            }
+            body.truncate(end);
+            body.push('…');
+        }
+
+        Self {
+            status: response.status,
+            headers,
+            body,
+        }
+    }
+}
+
+/// Why the API could not process one image of a batch, e.g. because its URL could not
+/// be fetched.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{code}: {message}")]
+pub struct ApiStatus {
+    /// A `google.rpc.Code`, e.g. `3` for `INVALID_ARGUMENT`.
+    #[serde(default)]
+    pub code: i32,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub details: Vec<Value>,
+}
+
+/// The `error` object the API answers with.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct GoogleApiError {
+    /// The HTTP status code, e.g. `403`.
+    #[serde(default)]
+    pub code: i32,
+    /// The canonical status, e.g. `PERMISSION_DENIED`.
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub message: String,
+    /// Machine-readable details such as `google.rpc.ErrorInfo`, each tagged with `@type`.
+    #[serde(default)]
+    pub details: Vec<Value>,
+}
+
+impl GoogleApiError {
+    /// Whether a quota or rate limit was exceeded.
+    pub fn is_quota_exceeded(&self) -> bool {
+        self.code == 429 || self.status.as_deref() == Some("RESOURCE_EXHAUSTED")
+    }
+}
+
+/// What an [`Error::QuotaExceeded`] ran into, as far as the API told in the `details` of
+/// its error and the `Retry-After` header.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QuotaViolation {
+    /// e.g. `vision.googleapis.com/default_requests`
+    pub quota_metric: Option<String>,
+    /// e.g. `DefaultRequestsPerMinutePerProject`
+    pub quota_limit: Option<String>,
+    /// The limit, e.g. `1800`.
+    pub quota_limit_value: Option<String>,
+    /// The `description` of a `google.rpc.QuotaFailure`.
+    pub description: Option<String>,
+    /// How long the API asked to wait.
+    pub retry_delay: Option<Duration>,
+}
+
+impl QuotaViolation {
+    pub(crate) fn new(error: Option<&GoogleApiError>, response: &HttpResponse) -> Self {
+        let mut quota = Self {
+            retry_delay: Outcome::Response(response).retry_after(),
+            ..Self::default()
+        };
+        let details = error
+            .map(|error| error.details.as_slice())
+            .unwrap_or_default();
+        for detail in details {
+            match detail["@type"].as_str().unwrap_or_default() {
+                "type.googleapis.com/google.rpc.ErrorInfo" => {
+                    let metadata = &detail["metadata"];
+                    let string = |key: &str| metadata[key].as_str().map(str::to_string);
+                    quota.quota_metric = string("quota_metric").or(quota.quota_metric);
+                    quota.quota_limit = string("quota_limit").or(quota.quota_limit);
+                    quota.quota_limit_value =
+                        string("quota_limit_value").or(quota.quota_limit_value);
+                }
+                "type.googleapis.com/google.rpc.QuotaFailure" => {
+                    quota.description = detail["violations"][0]["description"]
+                        .as_str()
+                        .map(str::to_string)
+                        .or(quota.description);
+                }
+                "type.googleapis.com/google.rpc.RetryInfo" => {
+                    // A protobuf Duration in JSON, e.g. "30s" or "1.5s"
+                    let delay = detail["retryDelay"].as_str().and_then(|delay| {
+                        Duration::try_from_secs_f64(delay.strip_suffix('s')?.parse().ok()?).ok()
+                    });
+                    quota.retry_delay = delay.or(quota.retry_delay);
+                }
+                _ => {}
+            }
+        }
+
+        quota
+    }
+}
+
+impl std::fmt::Display for GoogleApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.status {
+            Some(status) => write!(f, "{} {}: {}", self.code, status, self.message),
+            None => write!(f, "{}: {}", self.code, self.message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ApiStatus, Error, ErrorClass, GoogleApiError, HttpFailure, QuotaViolation};
+    use crate::HttpResponse;
+    use serde_json::json;
+    use std::time::Duration;
+
+    #[test]
+    fn http_failure() {
+        let response = HttpResponse {
+            status: 403,
+            headers: vec![
+                ("X-Debug-Tracking-Id".to_string(), "123".to_string()),
+                ("set-cookie".to_string(), "session".to_string()),
+                ("retry-after".to_string(), "1".to_string()),
+            ],
+            body: "あ".repeat(400).into_bytes(),
+        };
+
+        let failure = HttpFailure::new(&response);
+        assert_eq!(failure.status, 403);
+        assert_eq!(
+            failure.headers,
+            [
+                ("X-Debug-Tracking-Id".to_string(), "123".to_string()),
+                ("retry-after".to_string(), "1".to_string()),
+            ]
+        );
+        assert_eq!(failure.body, format!("{}…", "あ".repeat(341)));
+    }
+
+    #[test]
+    fn google_api_error() {
+        let error: GoogleApiError = serde_json::from_value(json!({
+            "code": 403,
+            "message": "Cloud Vision API has not been used in project 123.",
+            "status": "PERMISSION_DENIED",
+            "details": [{
+                "@type": "type.googleapis.com/google.rpc.ErrorInfo",
+                "reason": "SERVICE_DISABLED"
+            }]
+        }))
+        .unwrap();
+
+        assert_eq!(error.code, 403);
+        assert_eq!(error.status.as_deref(), Some("PERMISSION_DENIED"));
+        assert_eq!(error.details[0]["reason"], "SERVICE_DISABLED");
+        assert_eq!(
+            error.to_string(),
+            "403 PERMISSION_DENIED: Cloud Vision API has not been used in project 123."
+        );
+    }
+
+    #[test]
+    fn classes() {
+        let failure = |status| {
+            Box::new(HttpFailure::new(&HttpResponse {
+                status,
+                headers: vec![],
+                body: vec![],
+            }))
+        };
+        let refused = Error::http(std::io::Error::from(std::io::ErrorKind::ConnectionRefused));
+        let unavailable = Error::Image(ApiStatus {
+            code: 14,
+            message: String::new(),
+            details: vec![],
+        });
+
+        assert_eq!(refused.class(), ErrorClass::Transport);
+        assert!(refused.is_retryable());
+        assert!(!Error::http("relative URL without a base").is_retryable());
+        assert!(Error::Timeout.is_retryable());
+        assert_eq!(Error::Status(failure(503)).class(), ErrorClass::Api);
+        assert!(Error::Status(failure(503)).is_retryable());
+        assert!(!Error::Status(failure(403)).is_retryable());
+        assert!(unavailable.is_retryable());
+        assert_eq!(Error::decode("invalid json").class(), ErrorClass::Parse);
+        assert!(!Error::decode("invalid json").is_retryable());
+        assert_eq!(Error::CircuitOpen.class(), ErrorClass::Other);
+
+        let input = Error::Input {
+            index: 0,
+            tag: None,
+            error: Box::new(refused),
+        };
+        assert_eq!(input.class(), ErrorClass::Transport);
+        assert!(input.is_retryable());
+    }
+
+    #[test]
+    fn quota_violation() {
+        let error: GoogleApiError = serde_json::from_value(json!({
+            "code": 429,
+            "message": "Quota exceeded for quota metric 'Requests'.",
+            "status": "RESOURCE_EXHAUSTED",
+            "details": [{
+                "@type": "type.googleapis.com/google.rpc.ErrorInfo",
+                "reason": "RATE_LIMIT_EXCEEDED",
+                "metadata": {
+                    "quota_metric": "vision.googleapis.com/default_requests",
+                    "quota_limit": "DefaultRequestsPerMinutePerProject",
+                    "quota_limit_value": "1800"
+                }
+            }, {
+                "@type": "type.googleapis.com/google.rpc.RetryInfo",
+                "retryDelay": "1.5s"
+            }]
+        }))
+        .unwrap();
+        let response = HttpResponse {
+            status: 429,
+            headers: vec![("Retry-After".to_string(), "30".to_string())],
+            body: vec![],
+        };
+
+        assert!(error.is_quota_exceeded());
+        assert_eq!(
+            QuotaViolation::new(Some(&error), &response),
+            QuotaViolation {
+                quota_metric: Some("vision.googleapis.com/default_requests".to_string()),
+                quota_limit: Some("DefaultRequestsPerMinutePerProject".to_string()),
+                quota_limit_value: Some("1800".to_string()),
+                description: None,
+                retry_delay: Some(Duration::from_millis(1500)),
+            }
+        );
+        assert_eq!(
+            QuotaViolation::new(None, &response).retry_delay,
+            Some(Duration::from_secs(30))
+        );
+    }
+}
@@ -0,0 +1,214 @@
+//! Conversions of OCR results into formats other tools read.
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! # let response = gcv_client::Response::from_file("photo.json")?;
+//! use gcv_client::export;
+//!
+//! if let Some(full_text) = response.full_text_annotations() {
+//!     std::fs::write("photo.hocr", export::hocr(full_text))?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{BreakType, FullTextAnnotation, Rect, Word};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Renders `full_text` as hOCR, the HTML format of Tesseract that viewers and PDF tools
+/// accept: `ocr_page`, `ocr_carea`, `ocr_par`, `ocr_line` and `ocrx_word` elements with
+/// their boxes, and the confidence of each word as `x_wconf`.
+///
+/// Lines end at the line breaks the API detected after a word.
+pub fn hocr(full_text: &FullTextAnnotation) -> String {
+    let mut html = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<!DOCTYPE html PUBLIC \"-//W3C//DTD XHTML 1.0 Transitional//EN\" ",
+        "\"http://www.w3.org/TR/xhtml1/DTD/xhtml1-transitional.dtd\">\n",
+        "<html xmlns=\"http://www.w3.org/1999/xhtml\">\n",
+        " <head>\n",
+        "  <title></title>\n",
+        "  <meta http-equiv=\"Content-Type\" content=\"text/html; charset=utf-8\"/>\n",
+        "  <meta name=\"ocr-system\" content=\"gcv-client\"/>\n",
+        "  <meta name=\"ocr-capabilities\" ",
+        "content=\"ocr_page ocr_carea ocr_par ocr_line ocrx_word\"/>\n",
+        " </head>\n",
+        " <body>\n",
+    ));
+    let mut ids = Ids::default();
+
+    for (page_no, page) in full_text.pages.iter().enumerate() {
+        let page_box = Rect::new(0, 0, page.width.into(), page.height.into());
+        // Writing to a String can't fail.
+        let _ = writeln!(
+            html,
+            "  <div class=\"ocr_page\" id=\"{}\" title=\"{}; ppageno {}\">",
+            ids.next("page"),
+            bbox(&page_box),
+            page_no
+        );
+
+        for block in &page.blocks {
+            let _ = writeln!(
+                html,
+                "   <div class=\"ocr_carea\" id=\"{}\" title=\"{}\">",
+                ids.next("block"),
+                bbox(&block.bounding_box.rect())
+            );
+
+            for paragraph in &block.paragraphs {
+                let _ = writeln!(
+                    html,
+                    "    <p class=\"ocr_par\" id=\"{}\" title=\"{}\">",
+                    ids.next("par"),
+                    bbox(&paragraph.bounding_box.rect())
+                );
+
+                for line in lines(&paragraph.words) {
+                    let line_box = line
+                        .iter()
+                        .map(|word| word.bounding_box.rect())
+                        .reduce(|a, b| a.union(&b))
+                        .unwrap_or_default();
+                    let _ = write!(
+                        html,
+                        "     <span class=\"ocr_line\" id=\"{}\" title=\"{}\">",
+                        ids.next("line"),
+                        bbox(&line_box)
+                    );
+
+                    for (i, word) in line.iter().enumerate() {
+                        if i > 0 {
+                            html.push(' ');
+                        }
+                        write_word(&mut html, &mut ids, word);
+                    }
+                    html.push_str("</span>\n");
+                }
+                html.push_str("    </p>\n");
+            }
+            html.push_str("   </div>\n");
+        }
+        html.push_str("  </div>\n");
+    }
+
+    html.push_str(" </body>\n</html>\n");
+    html
+}
+
+fn write_word(html: &mut String, ids: &mut Ids, word: &Word) {
+    let mut text = word.text();
+    let last_break = word
+        .symbols
+        .last()
+        .and_then(|symbol| symbol.detected_break());
+    if last_break.is_some_and(|detected_break| detected_break.break_type == BreakType::Hyphen) {
+        text.push('-');
+    }
+
+    let _ = write!(
+        html,
+        "<span class=\"ocrx_word\" id=\"{}\" title=\"{}; x_wconf {}\"",
+        ids.next("word"),
+        bbox(&word.bounding_box.rect()),
+        (word.confidence * 100.0).round() as i64
+    );
+    if let Some(language) = word.language() {
+        let _ = write!(html, " lang=\"{}\"", escape(language));
+    }
+    let _ = write!(html, ">{}</span>", escape(&text));
+}
+
+/// Splits the words of a paragraph after each one followed by a line break.
+fn lines(words: &[Word]) -> impl Iterator<Item = &[Word]> {
+    words.split_inclusive(|word| {
+        word.symbols
+            .last()
+            .is_some_and(|symbol| symbol.break_text().ends_with('\n'))
+    })
+}
+
+fn bbox(rect: &Rect) -> String {
+    format!(
+        "bbox {} {} {} {}",
+        rect.left, rect.top, rect.right, rect.bottom
+    )
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Numbers the elements of each class in document order, e.g. `word_1`, `word_2`.
+#[derive(Default)]
+struct Ids {
+    counts: HashMap<&'static str, usize>,
+}
+
+impl Ids {
+    fn next(&mut self, class: &'static str) -> String {
+        let count = self.counts.entry(class).or_default();
+        *count += 1;
+        format!("{}_{}", class, count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::hocr;
+    use crate::test_fixtures::{full_text_json, word};
+    use crate::{FullTextAnnotation, Rect};
+    use serde_json::{json, Value};
+
+    fn word_with_break(text: &str, left: i64, top: i64, break_type: &str) -> Value {
+        let mut word = word(text, Rect::new(left, top, left + 40, top + 10));
+        word["confidence"] = json!(0.984);
+        word["symbols"][0]["property"] = json!({ "detectedBreak": { "type": break_type } });
+        word
+    }
+
+    #[test]
+    fn hocr_elements() {
+        let mut json = full_text_json(vec![vec![vec![
+            word_with_break("Fish", 10, 10, "SPACE"),
+            word_with_break("&", 60, 10, "EOL_SURE_SPACE"),
+            word_with_break("chips", 10, 30, "HYPHEN"),
+            word_with_break("<3", 10, 50, "LINE_BREAK"),
+        ]]]);
+        let page = &mut json["pages"][0];
+        page["width"] = json!(200);
+        page["height"] = json!(100);
+        page["blocks"][0]["boundingBox"] = json!({ "vertices": [
+            { "x": 10, "y": 10 }, { "x": 100, "y": 10 },
+            { "x": 100, "y": 40 }, { "x": 10, "y": 40 }
+        ] });
+        let full_text: FullTextAnnotation = serde_json::from_value(json).unwrap();
+
+        let html = hocr(&full_text);
+
+        assert!(html
+            .contains(r#"<div class="ocr_page" id="page_1" title="bbox 0 0 200 100; ppageno 0">"#));
+        assert!(html.contains(r#"<div class="ocr_carea" id="block_1" title="bbox 10 10 100 40">"#));
+        assert!(html.contains(concat!(
+            r#"<span class="ocr_line" id="line_1" title="bbox 10 10 100 20">"#,
+            r#"<span class="ocrx_word" id="word_1" title="bbox 10 10 50 20; x_wconf 98">Fish</span> "#,
+            r#"<span class="ocrx_word" id="word_2" title="bbox 60 10 100 20; x_wconf 98">&amp;</span>"#,
+            "</span>\n"
+        )));
+        assert!(html.contains(">chips-</span></span>"));
+        assert!(html.contains(r#"id="line_3""#));
+        assert!(html.contains(">&lt;3</span>"));
+        assert!(html.ends_with("</html>\n"));
+    }
+}
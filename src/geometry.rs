@@ -0,0 +1,321 @@
+use crate::{BoundingBox, Point, Polygon};
+
+/// An axis-aligned rectangle in pixel coordinates. `right` and `bottom` are exclusive.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Rect {
+    pub left: i64,
+    pub top: i64,
+    pub right: i64,
+    pub bottom: i64,
+}
+
+impl Rect {
+    pub fn new(left: i64, top: i64, right: i64, bottom: i64) -> Self {
+        Self {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    pub fn from_xywh(x: i64, y: i64, width: i64, height: i64) -> Self {
+        Self::new(x, y, x + width, y + height)
+    }
+
+    pub fn width(&self) -> i64 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> i64 {
+        self.bottom - self.top
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width() <= 0 || self.height() <= 0
+    }
+
+    /// The overlapping part of both rectangles, empty if they do not overlap.
+    pub fn intersection(&self, other: &Rect) -> Rect {
+        Rect {
+            left: self.left.max(other.left),
+            top: self.top.max(other.top),
+            right: self.right.min(other.right),
+            bottom: self.bottom.min(other.bottom),
+        }
+    }
+
+    /// Whether `other` lies entirely within this rectangle.
+    pub fn contains(&self, other: &Rect) -> bool {
+        self.left <= other.left
+            && self.top <= other.top
+            && other.right <= self.right
+            && other.bottom <= self.bottom
+    }
+
+    pub fn intersects(&self, other: &Rect) -> bool {
+        !self.intersection(other).is_empty()
+    }
+
+    pub fn contains_point(&self, point: Point) -> bool {
+        (self.left..self.right).contains(&point.x) && (self.top..self.bottom).contains(&point.y)
+    }
+
+    pub fn area(&self) -> i64 {
+        if self.is_empty() {
+            0
+        } else {
+            self.width() * self.height()
+        }
+    }
+
+    /// The intersection over union of both rectangles, from 0 (disjoint) to 1 (equal),
+    /// e.g. to match OCR results against ground truth boxes.
+    pub fn iou(&self, other: &Rect) -> f64 {
+        let intersection = self.intersection(other).area();
+        let union = self.area() + other.area() - intersection;
+        if union == 0 {
+            return 0.0;
+        }
+        intersection as f64 / union as f64
+    }
+
+    /// The distance from `point` to the nearest edge, 0 if the point is inside.
+    pub fn distance_to(&self, point: Point) -> f64 {
+        let dx = (self.left - point.x).max(point.x - self.right).max(0);
+        let dy = (self.top - point.y).max(point.y - self.bottom).max(0);
+        (dx as f64).hypot(dy as f64)
+    }
+
+    /// The smallest rectangle containing both rectangles.
+    pub fn union(&self, other: &Rect) -> Rect {
+        Rect {
+            left: self.left.min(other.left),
+            top: self.top.min(other.top),
+            right: self.right.max(other.right),
+            bottom: self.bottom.max(other.bottom),
+        }
+    }
+}
+
+impl Polygon {
+    /// The area enclosed by the pixel vertices, whatever their winding.
+    pub fn area(&self) -> f64 {
+        (shoelace(&self.vertices) / 2.0).abs()
+    }
+
+    /// The center of mass of the pixel vertices, or their mean if the polygon has no area.
+    pub fn centroid(&self) -> Point {
+        let vertices = &self.vertices;
+        if vertices.is_empty() {
+            return Point::default();
+        }
+
+        let twice_area = shoelace(vertices);
+        let (x, y) = if twice_area == 0.0 {
+            let n = vertices.len() as f64;
+            (
+                vertices.iter().map(|v| v.x as f64).sum::<f64>() / n,
+                vertices.iter().map(|v| v.y as f64).sum::<f64>() / n,
+            )
+        } else {
+            let (x, y) = edges(vertices).fold((0.0, 0.0), |(x, y), (a, b)| {
+                let cross = (a.x * b.y - b.x * a.y) as f64;
+                (
+                    x + (a.x + b.x) as f64 * cross,
+                    y + (a.y + b.y) as f64 * cross,
+                )
+            });
+            (x / (3.0 * twice_area), y / (3.0 * twice_area))
+        };
+        Point {
+            x: x.round() as i64,
+            y: y.round() as i64,
+        }
+    }
+
+    /// The rotation of the text in the box in degrees, clockwise in image coordinates,
+    /// from the edge between the first two vertices, which the API puts along the top
+    /// of the text. 0 for upright text, 180 for text upside down.
+    pub fn angle(&self) -> f64 {
+        top_edge_angle(&self.vertices)
+    }
+}
+
+impl BoundingBox {
+    /// The rotation of the text in the box in degrees, see [`Polygon::angle`].
+    pub fn angle(&self) -> f64 {
+        top_edge_angle(&self.vertices)
+    }
+}
+
+fn top_edge_angle(vertices: &[Point]) -> f64 {
+    match vertices {
+        [a, b, ..] => ((b.y - a.y) as f64).atan2((b.x - a.x) as f64).to_degrees(),
+        _ => 0.0,
+    }
+}
+
+/// Each vertex paired with the next one, the last with the first.
+pub(crate) fn edges(vertices: &[Point]) -> impl Iterator<Item = (Point, Point)> + '_ {
+    vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|(&a, &b)| (a, b))
+}
+
+/// Twice the signed area of the polygon.
+fn shoelace(vertices: &[Point]) -> f64 {
+    edges(vertices)
+        .map(|(a, b)| (a.x * b.y - b.x * a.y) as f64)
+        .sum()
+}
+
+impl From<&BoundingBox> for Rect {
+    fn from(bounding_box: &BoundingBox) -> Self {
+        bounding_box.rect()
+    }
+}
+
+impl From<&Polygon> for Rect {
+    fn from(polygon: &Polygon) -> Self {
+        polygon.rect()
+    }
+}
+
+/// The pixel vertices as the exterior of a polygon in the geo crate.
+#[cfg(feature = "geo")]
+impl From<&Polygon> for geo_types::Polygon<f64> {
+    fn from(polygon: &Polygon) -> Self {
+        geo_polygon(&polygon.vertices)
+    }
+}
+
+#[cfg(feature = "geo")]
+impl From<&BoundingBox> for geo_types::Polygon<f64> {
+    fn from(bounding_box: &BoundingBox) -> Self {
+        geo_polygon(&bounding_box.vertices)
+    }
+}
+
+#[cfg(feature = "geo")]
+impl From<Rect> for geo_types::Rect<f64> {
+    fn from(rect: Rect) -> Self {
+        geo_types::Rect::new(
+            (rect.left as f64, rect.top as f64),
+            (rect.right as f64, rect.bottom as f64),
+        )
+    }
+}
+
+/// Rounds outward to whole pixels.
+#[cfg(feature = "geo")]
+impl From<geo_types::Rect<f64>> for Rect {
+    fn from(rect: geo_types::Rect<f64>) -> Self {
+        let (min, max) = (rect.min(), rect.max());
+        Rect::new(
+            min.x.floor() as i64,
+            min.y.floor() as i64,
+            max.x.ceil() as i64,
+            max.y.ceil() as i64,
+        )
+    }
+}
+
+#[cfg(feature = "geo")]
+fn geo_polygon(vertices: &[Point]) -> geo_types::Polygon<f64> {
+    let exterior = vertices
+        .iter()
+        .map(|vertex| (vertex.x as f64, vertex.y as f64))
+        .collect();
+    geo_types::Polygon::new(exterior, vec![])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rect;
+    use crate::{Point, Polygon};
+
+    #[test]
+    fn intersection() {
+        let a = Rect::from_xywh(0, 0, 100, 50);
+
+        assert_eq!(
+            a.intersection(&Rect::new(50, 25, 200, 200)),
+            Rect::new(50, 25, 100, 50)
+        );
+        assert!(a.intersection(&Rect::new(100, 0, 150, 50)).is_empty());
+    }
+
+    #[test]
+    fn iou() {
+        let a = Rect::new(0, 0, 100, 100);
+
+        assert_eq!(a.iou(&a), 1.0);
+        assert_eq!(a.iou(&Rect::new(50, 0, 150, 100)), 1.0 / 3.0);
+        assert_eq!(a.iou(&Rect::new(100, 0, 200, 100)), 0.0);
+        assert_eq!(Rect::default().iou(&Rect::default()), 0.0);
+
+        assert!(a.contains_point(Point { x: 0, y: 99 }));
+        assert!(!a.contains_point(Point { x: 100, y: 50 }));
+    }
+
+    #[test]
+    fn polygon() {
+        let polygon = |vertices: &[(i64, i64)]| Polygon {
+            vertices: vertices.iter().map(|&(x, y)| Point { x, y }).collect(),
+            normalized_vertices: vec![],
+        };
+
+        let upright = polygon(&[(0, 0), (40, 0), (40, 10), (0, 10)]);
+        assert_eq!(upright.area(), 400.0);
+        assert_eq!(upright.centroid(), Point { x: 20, y: 5 });
+        assert_eq!(upright.angle(), 0.0);
+
+        // The same box upside down, and turned clockwise by 90°.
+        assert_eq!(
+            polygon(&[(40, 10), (0, 10), (0, 0), (40, 0)]).angle(),
+            180.0
+        );
+        let turned = polygon(&[(10, 0), (10, 40), (0, 40), (0, 0)]);
+        assert_eq!(turned.angle(), 90.0);
+        assert_eq!(turned.area(), 400.0);
+
+        let skewed = polygon(&[(0, 0), (100, 5), (99, 25), (-1, 20)]);
+        assert!((skewed.angle() - 2.86).abs() < 0.01);
+    }
+
+    #[cfg(feature = "geo")]
+    #[test]
+    fn geo() {
+        let polygon = Polygon {
+            vertices: vec![
+                Point { x: 0, y: 0 },
+                Point { x: 40, y: 0 },
+                Point { x: 40, y: 10 },
+                Point { x: 0, y: 10 },
+            ],
+            normalized_vertices: vec![],
+        };
+        let converted = geo_types::Polygon::from(&polygon);
+        let exterior: Vec<_> = converted.exterior().points().map(|p| p.x_y()).collect();
+        assert_eq!(
+            exterior,
+            [
+                (0.0, 0.0),
+                (40.0, 0.0),
+                (40.0, 10.0),
+                (0.0, 10.0),
+                (0.0, 0.0)
+            ]
+        );
+        assert!(converted.interiors().is_empty());
+
+        let rect = geo_types::Rect::from(Rect::new(10, 20, 30, 40));
+        assert_eq!(rect.min().x_y(), (10.0, 20.0));
+        assert_eq!(rect.max().x_y(), (30.0, 40.0));
+        assert_eq!(Rect::from(rect), Rect::new(10, 20, 30, 40));
+        let rect = geo_types::Rect::new((0.5, 1.0), (10.2, 19.9));
+        assert_eq!(Rect::from(rect), Rect::new(0, 1, 11, 20));
+    }
+}
@@ -0,0 +1,273 @@
+use crate::{FullTextAnnotation, Point, Rect, Symbol, Word};
+
+/// Children per node of the tree.
+const NODE_SIZE: usize = 16;
+
+/// A spatial index over the words of a [`FullTextAnnotation`], for repeated queries such
+/// as hit-testing in a viewer, which would otherwise scan every word each time.
+///
+/// It is a static R-tree packed with the sort-tile-recursive algorithm: the words are
+/// sorted into tiles of neighbours, and the `i`-th node of a level covers children
+/// `i * NODE_SIZE..(i + 1) * NODE_SIZE` of the level below.
+/// ```no_run
+/// # fn f(full_text: gcv_client::FullTextAnnotation) {
+/// use gcv_client::{AnnotationIndex, Point};
+///
+/// let index = AnnotationIndex::build(&full_text);
+/// if let Some(symbol) = index.symbol_at(Point { x: 120, y: 48 }) {
+///     println!("clicked {}", symbol.text);
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AnnotationIndex<'a> {
+    words: Vec<(Rect, &'a Word)>,
+    /// The bounds of the nodes, from the level above the words up to the root.
+    levels: Vec<Vec<Rect>>,
+}
+
+impl<'a> AnnotationIndex<'a> {
+    pub fn build(full_text: &'a FullTextAnnotation) -> Self {
+        let mut words: Vec<_> = full_text
+            .words()
+            .map(|word| (word.bounding_box.rect(), word))
+            .collect();
+        sort_tiles(&mut words);
+
+        // Without words there are no nodes, not even a root.
+        let mut levels: Vec<Vec<Rect>> = vec![];
+        if !words.is_empty() {
+            let mut bounds: Vec<Rect> = words.iter().map(|(rect, _)| *rect).collect();
+            loop {
+                bounds = bounds
+                    .chunks(NODE_SIZE)
+                    .map(|chunk| chunk.iter().fold(chunk[0], |a, b| a.union(b)))
+                    .collect();
+                levels.push(bounds.clone());
+                if bounds.len() == 1 {
+                    break;
+                }
+            }
+        }
+
+        Self { words, levels }
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// The words whose box contains `point`, usually at most one.
+    pub fn words_at(&self, point: Point) -> Vec<&'a Word> {
+        let area = Rect::new(point.x, point.y, point.x, point.y);
+        self.query(area, |rect| rect.contains_point(point))
+    }
+
+    /// The symbol whose box contains `point`, e.g. to place a cursor in the text.
+    pub fn symbol_at(&self, point: Point) -> Option<&'a Symbol> {
+        self.words_at(point).into_iter().find_map(|word| {
+            word.symbols
+                .iter()
+                .find(|symbol| symbol.bounding_box.rect().contains_point(point))
+        })
+    }
+
+    /// The words lying entirely within `rect`, like
+    /// [`FullTextAnnotation::words_in_rect`].
+    pub fn words_in_rect(&self, rect: Rect) -> Vec<&'a Word> {
+        self.query(rect, |word| rect.contains(word))
+    }
+
+    /// The words overlapping `rect`, like [`FullTextAnnotation::words_intersecting`].
+    pub fn words_intersecting(&self, rect: Rect) -> Vec<&'a Word> {
+        self.query(rect, |word| rect.intersects(word))
+    }
+
+    /// The words in nodes touching `area` that pass `matches`, in the order of the index.
+    fn query(&self, area: Rect, matches: impl Fn(&Rect) -> bool) -> Vec<&'a Word> {
+        let mut found = vec![];
+        let Some(root) = self.levels.len().checked_sub(1) else {
+            return found;
+        };
+
+        // (level, node) pairs left to visit, from the root down
+        let mut stack = vec![(root, 0)];
+        while let Some((level, node)) = stack.pop() {
+            if !touches(&self.levels[level][node], &area) {
+                continue;
+            }
+            let children = node * NODE_SIZE..(node + 1) * NODE_SIZE;
+            if level == 0 {
+                let end = children.end.min(self.words.len());
+                found.extend(
+                    self.words[children.start..end]
+                        .iter()
+                        .filter(|(rect, _)| matches(rect))
+                        .map(|(_, word)| *word),
+                );
+            } else {
+                let end = children.end.min(self.levels[level - 1].len());
+                stack.extend((children.start..end).rev().map(|child| (level - 1, child)));
+            }
+        }
+        found
+    }
+}
+
+/// Whether the rectangles overlap or share an edge, so empty boxes aren't pruned.
+fn touches(a: &Rect, b: &Rect) -> bool {
+    a.left <= b.right && b.left <= a.right && a.top <= b.bottom && b.top <= a.bottom
+}
+
+/// Sorts `items` into vertical slices by their center, and each slice from top to
+/// bottom, so consecutive runs of [`NODE_SIZE`] items are compact tiles.
+fn sort_tiles<T>(items: &mut [(Rect, T)]) {
+    let center_x = |rect: &Rect| rect.left + rect.right;
+    let center_y = |rect: &Rect| rect.top + rect.bottom;
+
+    let leaves = items.len().div_ceil(NODE_SIZE);
+    let slices = (leaves as f64).sqrt().ceil() as usize;
+    let slice_len = (slices * NODE_SIZE).max(1);
+
+    items.sort_by_key(|(rect, _)| center_x(rect));
+    for slice in items.chunks_mut(slice_len) {
+        slice.sort_by_key(|(rect, _)| center_y(rect));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AnnotationIndex;
+    use crate::test_fixtures::{full_text, rotated_word};
+    use crate::{FullTextAnnotation, Point, Rect, Word};
+    use serde_json::{json, Value};
+    use std::collections::BTreeSet;
+
+    fn word(text: &str, left: i64, top: i64) -> Value {
+        let bounding_box = |left: i64, right: i64| {
+            json!({ "vertices": [
+                { "x": left, "y": top }, { "x": right, "y": top },
+                { "x": right, "y": top + 10 }, { "x": left, "y": top + 10 }
+            ] })
+        };
+        json!({
+            "boundingBox": bounding_box(left, left + 20),
+            "symbols": [
+                { "boundingBox": bounding_box(left, left + 10), "text": &text[..1] },
+                { "boundingBox": bounding_box(left + 10, left + 20), "text": &text[1..] }
+            ]
+        })
+    }
+
+    #[test]
+    fn index() {
+        // A page of 40 lines of 30 words.
+        let words: Vec<_> = (0..40)
+            .flat_map(|row| (0..30).map(move |column| word("ab", column * 30, row * 15)))
+            .collect();
+        let full_text = full_text(words);
+
+        let index = AnnotationIndex::build(&full_text);
+        assert_eq!(index.len(), 1200);
+
+        let hit = index.words_at(Point { x: 65, y: 32 });
+        assert_eq!(hit.len(), 1);
+        assert_eq!(hit[0].bounding_box.rect(), Rect::new(60, 30, 80, 40));
+        assert_eq!(index.symbol_at(Point { x: 75, y: 32 }).unwrap().text, "b");
+        assert!(index.words_at(Point { x: 85, y: 32 }).is_empty());
+
+        for rect in [Rect::new(50, 20, 200, 100), Rect::new(-10, 580, 35, 800)] {
+            assert_eq!(
+                index.words_in_rect(rect).len(),
+                full_text.words_in_rect(rect).count()
+            );
+            assert_eq!(
+                index.words_intersecting(rect).len(),
+                full_text.words_intersecting(rect).count()
+            );
+        }
+
+        let empty = FullTextAnnotation {
+            text: String::new(),
+            pages: vec![],
+        };
+        let index = AnnotationIndex::build(&empty);
+        assert!(index.levels.is_empty());
+        assert!(index.words_at(Point::default()).is_empty());
+    }
+
+    #[test]
+    fn same_as_linear_scan() {
+        // xorshift, for reproducible boxes without a dependency
+        let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+        let mut random = move |bound: i64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed % bound as u64) as i64
+        };
+
+        // 150 lines of 140 words, jittered so that neighbours overlap, and every third
+        // word rotated.
+        let mut words = vec![];
+        for row in 0..150 {
+            for column in 0..140 {
+                let (x, y) = (column * 25 + random(15), row * 12 + random(8));
+                let (width, height) = (10 + random(30), 8 + random(6));
+                let (dx, dy) = match random(3) {
+                    0 => (random(9) - 4, random(9) - 4),
+                    _ => (0, 0),
+                };
+                words.push(rotated_word(
+                    "w",
+                    [
+                        (x, y),
+                        (x + width, y + dy),
+                        (x + width - dx, y + dy + height),
+                        (x - dx, y + height),
+                    ],
+                ));
+            }
+        }
+        let full_text = full_text(words);
+        let index = AnnotationIndex::build(&full_text);
+        assert_eq!(index.len(), 21_000);
+
+        let set = |words: Vec<&Word>| -> BTreeSet<_> {
+            words.into_iter().map(|word| word as *const Word).collect()
+        };
+        let mut found = 0;
+        for _ in 0..100 {
+            let point = Point {
+                x: random(3600) - 50,
+                y: random(1900) - 50,
+            };
+            let expected: Vec<_> = full_text
+                .words()
+                .filter(|word| word.bounding_box.rect().contains_point(point))
+                .collect();
+            assert_eq!(set(index.words_at(point)), set(expected), "{:?}", point);
+
+            let (left, top) = (random(3600) - 50, random(1900) - 50);
+            let rect = Rect::new(left, top, left + random(400), top + random(200));
+            found += index.words_in_rect(rect).len();
+            assert_eq!(
+                set(index.words_in_rect(rect)),
+                set(full_text.words_in_rect(rect).collect()),
+                "{:?}",
+                rect
+            );
+            assert_eq!(
+                set(index.words_intersecting(rect)),
+                set(full_text.words_intersecting(rect).collect()),
+                "{:?}",
+                rect
+            );
+        }
+        assert!(found > 1000, "{}", found);
+    }
+}
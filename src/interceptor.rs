@@ -0,0 +1,94 @@
+use crate::{Error, HttpRequest, HttpResponse, Result};
+use async_trait::async_trait;
+
+/// A layer around every HTTP call of a [`Client`](crate::Client), e.g. to sign requests,
+/// add custom auth headers or inject trace context.
+///
+/// Interceptors see each attempt, including retries. They run in the order they were
+/// added before a request is sent, and in reverse order once its response arrives.
+/// ```no_run
+/// # fn main() -> anyhow::Result<()> {
+/// use gcv_client::{HttpRequest, Interceptor};
+///
+/// struct TraceContext;
+///
+/// #[async_trait::async_trait]
+/// impl Interceptor for TraceContext {
+///     async fn before_send(&self, request: &mut HttpRequest) -> gcv_client::Result<()> {
+///         request.headers.push(("traceparent".to_string(), "00-...".to_string()));
+///         Ok(())
+///     }
+/// }
+///
+/// let client = gcv_client::Client::builder()
+///     .credential(gcv_client::Credential::access_token("ya29..."))
+///     .interceptor(TraceContext)
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[async_trait]
+pub trait Interceptor: Send + Sync {
+    /// Inspects or modifies `request` after the credential was added. An error fails
+    /// the attempt without sending it.
+    async fn before_send(&self, _request: &mut HttpRequest) -> Result<()> {
+        Ok(())
+    }
+
+    /// Observes the outcome of sending a request: its response, whatever the status,
+    /// or the error when no response arrived.
+    async fn after_response(&self, _response: Result<&HttpResponse, &Error>) {}
+}
+
+#[cfg(all(test, feature = "reqwest"))]
+mod tests {
+    use super::Interceptor;
+    use crate::test_server::{image_request, TestServer};
+    use crate::{HttpRequest, HttpResponse};
+    use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
+
+    struct Tag(&'static str, Arc<Mutex<Vec<String>>>);
+
+    #[async_trait]
+    impl Interceptor for Tag {
+        async fn before_send(&self, request: &mut HttpRequest) -> crate::Result<()> {
+            self.1.lock().unwrap().push(format!("before {}", self.0));
+            request
+                .headers
+                .push(("x-tag".to_string(), self.0.to_string()));
+            Ok(())
+        }
+
+        async fn after_response(&self, response: Result<&HttpResponse, &crate::Error>) {
+            let status = response.map(|response| response.status).unwrap_or(0);
+            self.1
+                .lock()
+                .unwrap()
+                .push(format!("after {} {}", self.0, status));
+        }
+    }
+
+    #[tokio::test]
+    async fn interceptors() {
+        let server = TestServer::start(vec![(200, r#"{"responses": [{}]}"#.to_string())]).await;
+        let log = Arc::new(Mutex::new(vec![]));
+        let client = server
+            .client_builder()
+            .interceptor(Tag("a", log.clone()))
+            .interceptor(Tag("b", log.clone()))
+            .build()
+            .unwrap();
+        let request = image_request();
+
+        client.annotate(&request).await.unwrap();
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            ["before a", "before b", "after b 200", "after a 200"]
+        );
+        let request = server.requests().await[0].to_lowercase();
+        assert!(request.contains("x-tag: a\r\n"));
+        assert!(request.contains("x-tag: b\r\n"));
+    }
+}
@@ -1,209 +1,1665 @@
-use anyhow::Context as _;
+use image::codecs::jpeg::JpegEncoder;
 use image::codecs::png::PngEncoder;
-use image::{DynamicImage, ImageEncoder};
+use image::imageops::FilterType;
+use image::{ColorType, DynamicImage, ImageEncoder, ImageFormat};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 use serde_json::Value;
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::Arc;
 
-const CLOUD_VISION_URI: &str = "https://vision.googleapis.com/v1/images:annotate";
+mod auth;
+pub mod borrowed;
+mod builder;
+mod circuit_breaker;
+mod concurrency;
+mod confidence;
+mod decode;
+mod dedup;
+mod error;
+pub mod export;
+mod geometry;
+mod index;
+mod interceptor;
+mod lines;
+mod reading_order;
+mod redact;
+mod request;
+mod retry;
+mod search;
+mod sidecar;
+mod skew;
+mod spatial;
+#[cfg(test)]
+mod test_fixtures;
+#[cfg(test)]
+mod test_server;
+mod tiling;
+mod transform;
+mod transport;
+
+#[cfg(feature = "gcp-auth")]
+pub use auth::GcpAuth;
+pub use auth::{
+    AuthorizedUser, AuthorizedUserKey, Credential, ImpersonatedServiceAccount, MetadataServer,
+    ServiceAccount, ServiceAccountKey, TokenProvider, CLOUD_PLATFORM_SCOPE,
+};
+pub use builder::{ClientBuilder, Region};
+pub use circuit_breaker::{CircuitBreaker, CircuitState};
+pub use concurrency::Concurrency;
+pub use decode::SchemaMismatch;
+pub use dedup::dedup_text_annotations;
+pub use error::{
+    ApiStatus, BoxError, Error, ErrorClass, GoogleApiError, HttpFailure, QuotaViolation, Result,
+};
+pub use geometry::Rect;
+pub use index::AnnotationIndex;
+pub use interceptor::Interceptor;
+pub use lines::Line;
+pub use reading_order::sort_reading_order;
+pub use request::{
+    AnnotateImageRequest, AnnotateRequestBuilder, CropHintsParams, Feature, FeatureRequest,
+    ImageContext, ImageSource, LatLongRect, TextDetectionParams, WebDetectionParams,
+};
+pub use retry::{ExponentialBackoff, NoRetry, Outcome, RetryEvent, RetryPolicy};
+pub use search::TextMatch;
+pub use sidecar::{sidecar_path, SIDECAR_VERSION};
+pub use skew::{Orientation, Skew};
+pub use tiling::TileOptions;
+pub use tokio_util::sync::CancellationToken;
+pub use transform::Transform;
+pub use transport::{HttpRequest, HttpResponse, HttpTransport, Method};
+
+pub const DEFAULT_ENDPOINT: &str = "https://vision.googleapis.com";
+
+/// Maximum number of images the API accepts in one `images:annotate` call.
+pub const MAX_BATCH_SIZE: usize = 16;
+
+/// Maximum size of an inline image before base64 encoding.
+pub const MAX_IMAGE_SIZE: usize = 10 * 1024 * 1024;
 
+/// Maximum total size of the base64 encoded images in one `images:annotate` call.
+pub const MAX_REQUEST_SIZE: usize = 20 * 1024 * 1024;
+
+const MB: f64 = 1024.0 * 1024.0;
+
+/// How [`ImageGCV`] encodes decoded pixels before upload.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum EncodeAs {
+    /// Lossless, but large for photos and scans.
+    #[default]
+    Png,
+    /// Lossy with `quality` in `1..=100`. Drops the alpha channel.
+    /// Around 85 usually keeps OCR accuracy while shrinking payloads a lot.
+    Jpeg { quality: u8 },
+}
+
+/// Preprocessing applied by [`ImageGCV::from_image_with`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct EncodeOptions {
+    pub encode_as: EncodeAs,
+    /// Downscale images whose longest edge exceeds this many pixels, keeping the aspect ratio.
+    /// OCR accuracy does not improve past roughly 4096px, while payload and latency do grow.
+    pub max_edge: Option<u32>,
+    /// Rotate the pixels upright according to the EXIF orientation tag, as photos from
+    /// phones are often stored sideways. Only applies when reading encoded files.
+    pub apply_exif_orientation: bool,
+}
+
+#[derive(Clone)]
 pub struct ImageGCV {
     base64_data: String,
+    transform: Transform,
 }
 
 impl ImageGCV {
-    pub fn from_image(image: &DynamicImage) -> anyhow::Result<Self> {
+    pub fn from_image(image: &DynamicImage) -> Result<Self> {
+        Self::from_image_as(image, EncodeAs::Png)
+    }
+
+    pub fn from_image_as(image: &DynamicImage, encode_as: EncodeAs) -> Result<Self> {
+        Self::from_image_with(
+            image,
+            &EncodeOptions {
+                encode_as,
+                ..Default::default()
+            },
+        )
+    }
+
+    pub fn from_image_with(image: &DynamicImage, options: &EncodeOptions) -> Result<Self> {
+        match options.max_edge {
+            Some(max_edge) if image.width().max(image.height()) > max_edge => {
+                if max_edge == 0 {
+                    return Err(Error::InvalidInput("max_edge must be positive".to_string()));
+                }
+                let resized = image.resize(max_edge, max_edge, FilterType::Lanczos3);
+                let transform = Transform::scale(
+                    image.width() as f64 / resized.width() as f64,
+                    image.height() as f64 / resized.height() as f64,
+                );
+
+                Ok(Self {
+                    transform,
+                    ..Self::encode(&resized, options.encode_as)?
+                })
+            }
+            _ => Self::encode(image, options.encode_as),
+        }
+    }
+
+    /// Maps coordinates in the uploaded image, which the API reports, back to the
+    /// original image. This is the identity unless the image was downscaled or rotated.
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn encode(image: &DynamicImage, encode_as: EncodeAs) -> Result<Self> {
         let mut buf = vec![];
-        {
-            let encoder = PngEncoder::new(&mut buf);
-
-            encoder.write_image(
-                image.as_bytes(),
-                image.width(),
-                image.height(),
-                image.color(),
-            )?;
+        match encode_as {
+            EncodeAs::Png => {
+                let encoder = PngEncoder::new(&mut buf);
+
+                encoder.write_image(
+                    image.as_bytes(),
+                    image.width(),
+                    image.height(),
+                    image.color(),
+                )?;
+            }
+            EncodeAs::Jpeg { quality } => {
+                if !(1..=100).contains(&quality) {
+                    return Err(Error::InvalidInput(format!(
+                        "JPEG quality must be in 1..=100, got {}",
+                        quality
+                    )));
+                }
+                let encoder = JpegEncoder::new_with_quality(&mut buf, quality);
+
+                match image {
+                    DynamicImage::ImageLuma8(_) | DynamicImage::ImageRgb8(_) => encoder
+                        .write_image(
+                            image.as_bytes(),
+                            image.width(),
+                            image.height(),
+                            image.color(),
+                        )?,
+                    _ => {
+                        let rgb = image.to_rgb8();
+                        encoder.write_image(
+                            rgb.as_raw(),
+                            rgb.width(),
+                            rgb.height(),
+                            ColorType::Rgb8,
+                        )?
+                    }
+                }
+            }
         }
 
+        check_image_size(buf.len())?;
+
         Ok(Self {
             base64_data: base64::encode(buf),
+            transform: Transform::IDENTITY,
+        })
+    }
+
+    /// Uses already encoded PNG, JPEG, WebP or GIF bytes as they are, without re-encoding.
+    pub fn from_encoded_bytes(bytes: impl AsRef<[u8]>) -> Result<Self> {
+        let bytes = bytes.as_ref();
+        let format = image::guess_format(bytes)?;
+
+        if !is_passthrough_format(format) {
+            return Err(Error::InvalidInput(format!(
+                "{:?} is not accepted as is, decode it and use ImageGCV::from_image",
+                format
+            )));
+        }
+
+        check_image_size(bytes.len())?;
+
+        Ok(Self {
+            base64_data: base64::encode(bytes),
+            transform: Transform::IDENTITY,
         })
     }
+
+    /// Reads an image file. PNG, JPEG, WebP and GIF files are sent as they are,
+    /// other formats supported by the `image` crate are decoded and re-encoded as PNG.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_path_with(path, &EncodeOptions::default())
+    }
+
+    /// Reads an image file, see [`ImageGCV::from_bytes_with`].
+    pub fn from_path_with(path: impl AsRef<Path>, options: &EncodeOptions) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|error| {
+            Error::encode(
+                anyhow::Error::new(error).context(format!("failed to read {}", path.display())),
+            )
+        })?;
+
+        Self::from_bytes_with(bytes, options)
+    }
+
+    /// Prepares encoded image bytes. They are sent as they are when no preprocessing is
+    /// needed and the format is accepted by the API, otherwise they are decoded,
+    /// preprocessed and re-encoded with `options.encode_as`.
+    pub fn from_bytes_with(bytes: impl AsRef<[u8]>, options: &EncodeOptions) -> Result<Self> {
+        let bytes = bytes.as_ref();
+        let orientation = if options.apply_exif_orientation {
+            exif_orientation(bytes)
+        } else {
+            1
+        };
+        let fits = |max_edge: u32| {
+            image::io::Reader::new(Cursor::new(bytes))
+                .with_guessed_format()
+                .ok()
+                .and_then(|reader| reader.into_dimensions().ok())
+                .is_some_and(|(width, height)| width.max(height) <= max_edge)
+        };
+
+        match image::guess_format(bytes) {
+            Ok(format)
+                if is_passthrough_format(format)
+                    && orientation == 1
+                    && options.max_edge.is_none_or(fits) =>
+            {
+                Self::from_encoded_bytes(bytes)
+            }
+            _ => {
+                let image = image::load_from_memory(bytes)?;
+                let (width, height) = (image.width(), image.height());
+                let image = match orientation {
+                    2 => image.fliph(),
+                    3 => image.rotate180(),
+                    4 => image.flipv(),
+                    5 => image.rotate90().fliph(),
+                    6 => image.rotate90(),
+                    7 => image.rotate270().fliph(),
+                    8 => image.rotate270(),
+                    _ => image,
+                };
+                let gcv_image = Self::from_image_with(&image, options)?;
+                let transform = gcv_image.transform.then(&Transform::from_exif_orientation(
+                    orientation,
+                    width,
+                    height,
+                ));
+
+                Ok(Self {
+                    transform,
+                    ..gcv_image
+                })
+            }
+        }
+    }
+}
+
+/// EXIF orientation tag of the image, `1` (upright) when absent.
+fn exif_orientation(bytes: &[u8]) -> u32 {
+    exif::Reader::new()
+        .read_from_container(&mut Cursor::new(bytes))
+        .ok()
+        .and_then(|exif| {
+            exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)?
+                .value
+                .get_uint(0)
+        })
+        .unwrap_or(1)
+}
+
+fn check_image_size(size: usize) -> Result<()> {
+    if size > MAX_IMAGE_SIZE {
+        return Err(Error::InvalidInput(format!(
+            "encoded image is {:.1} MB but the API accepts at most {:.0} MB; \
+             shrink it with EncodeAs::Jpeg or EncodeOptions::max_edge",
+            size as f64 / MB,
+            MAX_IMAGE_SIZE as f64 / MB
+        )));
+    }
+
+    Ok(())
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+fn check_request_size(requests: &[AnnotateImageRequest]) -> Result<()> {
+    let size: usize = requests
+        .iter()
+        .map(|request| match &request.image {
+            ImageSource::Content(content) => content.len(),
+            _ => 0,
+        })
+        .sum();
+
+    if size > MAX_REQUEST_SIZE {
+        return Err(Error::InvalidInput(format!(
+            "images in the request total {:.1} MB after base64 encoding but the API accepts \
+             at most {:.0} MB per call; split them into smaller batches",
+            size as f64 / MB,
+            MAX_REQUEST_SIZE as f64 / MB
+        )));
+    }
+
+    Ok(())
+}
+
+/// Formats the API accepts that are worth sending without re-encoding.
+fn is_passthrough_format(format: ImageFormat) -> bool {
+    matches!(
+        format,
+        ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::WebP | ImageFormat::Gif
+    )
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TextAnnotation {
     pub locale: Option<String>,
     pub description: String,
+    /// Empty when a field mask leaves it out.
+    #[serde(rename = "boundingPoly", default)]
+    pub bounding_poly: Polygon,
+    /// Only reported when requested with
+    /// [`AnnotateRequestBuilder::enable_text_detection_confidence_score`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
+}
+
+/// Result of label detection (and the other entity detections).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EntityAnnotation {
+    /// Opaque entity ID, usable with the Google Knowledge Graph Search API.
+    pub mid: Option<String>,
+    pub locale: Option<String>,
+    pub description: String,
+    #[serde(default)]
+    pub score: f64,
+    /// Relevancy of the label to the image, as opposed to the `score` of the detection itself.
+    #[serde(default)]
+    pub topicality: f64,
+    /// Region of the entity. Not produced for label detection.
+    #[serde(rename = "boundingPoly", skip_serializing_if = "Option::is_none")]
+    pub bounding_poly: Option<Polygon>,
+    /// Location of the entity. Landmark detection may report several
+    /// (e.g. the location of the scene and of the photographer).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locations: Vec<LocationInfo>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+pub struct LocationInfo {
+    #[serde(rename = "latLng")]
+    pub lat_lng: LatLng,
+}
+
+/// A latitude/longitude pair in degrees (WGS84).
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct LatLng {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// A bucketized likelihood, ordered from `Unknown` to `VeryLikely`.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Likelihood {
+    #[default]
+    Unknown,
+    VeryUnlikely,
+    Unlikely,
+    Possible,
+    Likely,
+    VeryLikely,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FaceAnnotation {
+    /// Bounds of the face including head and hair.
+    #[serde(rename = "boundingPoly")]
+    pub bounding_poly: Polygon,
+    /// Tighter bounds enclosing only the skin part of the face.
+    #[serde(rename = "fdBoundingPoly")]
+    pub fd_bounding_poly: Polygon,
+    #[serde(default)]
+    pub landmarks: Vec<FaceLandmark>,
+    #[serde(rename = "rollAngle", default)]
+    pub roll_angle: f64,
+    #[serde(rename = "panAngle", default)]
+    pub pan_angle: f64,
+    #[serde(rename = "tiltAngle", default)]
+    pub tilt_angle: f64,
+    #[serde(rename = "detectionConfidence", default)]
+    pub detection_confidence: f64,
+    #[serde(rename = "landmarkingConfidence", default)]
+    pub landmarking_confidence: f64,
+    #[serde(rename = "joyLikelihood", default)]
+    pub joy_likelihood: Likelihood,
+    #[serde(rename = "sorrowLikelihood", default)]
+    pub sorrow_likelihood: Likelihood,
+    #[serde(rename = "angerLikelihood", default)]
+    pub anger_likelihood: Likelihood,
+    #[serde(rename = "surpriseLikelihood", default)]
+    pub surprise_likelihood: Likelihood,
+    #[serde(rename = "underExposedLikelihood", default)]
+    pub under_exposed_likelihood: Likelihood,
+    #[serde(rename = "blurredLikelihood", default)]
+    pub blurred_likelihood: Likelihood,
+    #[serde(rename = "headwearLikelihood", default)]
+    pub headwear_likelihood: Likelihood,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FaceLandmark {
+    /// Landmark kind such as `LEFT_EYE` or `NOSE_TIP`.
+    #[serde(rename = "type")]
+    pub landmark_type: String,
+    pub position: Position,
+}
+
+/// A 3D position in the image, in pixels. `z` is the depth.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Default)]
+#[serde(default)]
+pub struct Position {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LocalizedObjectAnnotation {
+    pub mid: Option<String>,
+    #[serde(rename = "languageCode")]
+    pub language_code: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub score: f64,
+    /// Region of the object, given in `normalized_vertices`.
     #[serde(rename = "boundingPoly")]
     pub bounding_poly: Polygon,
 }
 
-#[derive(Deserialize, Debug)]
+/// Likelihood of each kind of explicit content in the image.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Default)]
+#[serde(default)]
+pub struct SafeSearchAnnotation {
+    pub adult: Likelihood,
+    /// The image is a modified version of a canonical image (e.g. a meme).
+    pub spoof: Likelihood,
+    pub medical: Likelihood,
+    pub violence: Likelihood,
+    pub racy: Likelihood,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ImageProperties {
+    #[serde(rename = "dominantColors")]
+    pub dominant_colors: DominantColors,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DominantColors {
+    #[serde(default)]
+    pub colors: Vec<ColorInfo>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+pub struct ColorInfo {
+    pub color: Color,
+    #[serde(default)]
+    pub score: f64,
+    /// Fraction of the image's pixels that have this color.
+    #[serde(rename = "pixelFraction", default)]
+    pub pixel_fraction: f64,
+}
+
+/// An RGB color with components in the range `0.0..=255.0`.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Color {
+    pub red: f64,
+    pub green: f64,
+    pub blue: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alpha: Option<f64>,
+}
+
+impl Color {
+    pub fn to_rgb(&self) -> [u8; 3] {
+        [self.red, self.green, self.blue].map(|c| c.round().clamp(0.0, 255.0) as u8)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CropHintsAnnotation {
+    #[serde(rename = "cropHints", default)]
+    pub crop_hints: Vec<CropHint>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CropHint {
+    #[serde(rename = "boundingPoly")]
+    pub bounding_poly: Polygon,
+    #[serde(default)]
+    pub confidence: f64,
+    /// Fraction of importance of this salient region with respect to the original image.
+    #[serde(rename = "importanceFraction", default)]
+    pub importance_fraction: f64,
+}
+
+/// Relevant information for the image from the Internet.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct WebDetection {
+    #[serde(rename = "webEntities")]
+    pub web_entities: Vec<WebEntity>,
+    #[serde(rename = "fullMatchingImages")]
+    pub full_matching_images: Vec<WebImage>,
+    /// Images with cropped or otherwise modified versions of the query image.
+    #[serde(rename = "partialMatchingImages")]
+    pub partial_matching_images: Vec<WebImage>,
+    #[serde(rename = "pagesWithMatchingImages")]
+    pub pages_with_matching_images: Vec<WebPage>,
+    #[serde(rename = "visuallySimilarImages")]
+    pub visually_similar_images: Vec<WebImage>,
+    /// The service's best guess as to the topic of the image.
+    #[serde(rename = "bestGuessLabels")]
+    pub best_guess_labels: Vec<WebLabel>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WebEntity {
+    #[serde(rename = "entityId")]
+    pub entity_id: Option<String>,
+    #[serde(default)]
+    pub score: f64,
+    pub description: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WebImage {
+    pub url: String,
+    #[serde(default)]
+    pub score: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WebPage {
+    pub url: String,
+    #[serde(default)]
+    pub score: f64,
+    #[serde(rename = "pageTitle")]
+    pub page_title: Option<String>,
+    #[serde(rename = "fullMatchingImages", default)]
+    pub full_matching_images: Vec<WebImage>,
+    #[serde(rename = "partialMatchingImages", default)]
+    pub partial_matching_images: Vec<WebImage>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WebLabel {
+    pub label: String,
+    #[serde(rename = "languageCode")]
+    pub language_code: Option<String>,
+}
+
+/// Result of `DOCUMENT_TEXT_DETECTION`: the text as pages of blocks of paragraphs of
+/// words of symbols.
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct FullTextAnnotation {
-    pages: Vec<Page>,
+    /// All of the text, with line breaks.
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub pages: Vec<Page>,
+}
+
+impl FullTextAnnotation {
+    /// The blocks of all pages, in reading order.
+    pub fn blocks(&self) -> impl Iterator<Item = &Block> + '_ {
+        self.pages.iter().flat_map(|page| &page.blocks)
+    }
+
+    pub fn paragraphs(&self) -> impl Iterator<Item = &Paragraph> + '_ {
+        self.blocks().flat_map(|block| &block.paragraphs)
+    }
+
+    /// The words of all pages, e.g. to collect their text with their boxes.
+    /// ```no_run
+    /// # fn f(full_text: gcv_client::FullTextAnnotation) {
+    /// for word in full_text.words() {
+    ///     println!("{} {:?}", word.text(), word.bounding_box.vertices);
+    /// }
+    /// # }
+    /// ```
+    pub fn words(&self) -> impl Iterator<Item = &Word> + '_ {
+        self.paragraphs().flat_map(|paragraph| &paragraph.words)
+    }
+
+    pub fn symbols(&self) -> impl Iterator<Item = &Symbol> + '_ {
+        self.words().flat_map(|word| &word.symbols)
+    }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Page {
-    blocks: Vec<Block>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub property: Option<TextProperty>,
+    /// In pixels; in points for PDFs.
+    #[serde(default)]
+    pub width: u32,
+    #[serde(default)]
+    pub height: u32,
+    #[serde(default)]
+    pub confidence: f64,
+    #[serde(default)]
+    pub blocks: Vec<Block>,
 }
 
-#[derive(Deserialize, Debug)]
+impl Page {
+    pub fn text(&self) -> String {
+        self.blocks.iter().map(Block::text).collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Block {
-    #[serde(rename = "blockType")]
-    block_type: String,
-    #[serde(rename = "boundingBox")]
-    bounding_box: BoundingBox,
-    confidence: f64,
-    paragraphs: Vec<Paragraph>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub property: Option<TextProperty>,
+    /// e.g. `TEXT`, `TABLE` or `PICTURE`.
+    #[serde(rename = "blockType", default)]
+    pub block_type: String,
+    #[serde(rename = "boundingBox", default)]
+    pub bounding_box: BoundingBox,
+    #[serde(default)]
+    pub confidence: f64,
+    #[serde(default)]
+    pub paragraphs: Vec<Paragraph>,
+}
+
+impl Block {
+    pub fn text(&self) -> String {
+        self.paragraphs.iter().map(Paragraph::text).collect()
+    }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Paragraph {
-    #[serde(rename = "boundingBox")]
-    bounding_box: BoundingBox,
-    confidence: f64,
-    words: Vec<Word>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub property: Option<TextProperty>,
+    #[serde(rename = "boundingBox", default)]
+    pub bounding_box: BoundingBox,
+    #[serde(default)]
+    pub confidence: f64,
+    #[serde(default)]
+    pub words: Vec<Word>,
+}
+
+impl Paragraph {
+    /// The words with the spaces and line breaks detected between them.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for word in &self.words {
+            word.push_text(&mut text, true);
+        }
+        text
+    }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Word {
-    #[serde(rename = "boundingBox")]
-    bounding_box: BoundingBox,
-    confidence: f64,
-    #[serde(rename = "symbols")]
-    symbols: Vec<Symbol>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub property: Option<TextProperty>,
+    #[serde(rename = "boundingBox", default)]
+    pub bounding_box: BoundingBox,
+    #[serde(default)]
+    pub confidence: f64,
+    #[serde(default)]
+    pub symbols: Vec<Symbol>,
 }
 
-#[derive(Deserialize, Debug)]
+impl Word {
+    /// The most confident language detected for the word, if any.
+    pub fn language(&self) -> Option<&str> {
+        self.property.as_ref()?.language()
+    }
+
+    /// The symbols of the word, without the break that follows it.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        self.push_text(&mut text, false);
+        text
+    }
+
+    /// Appends the symbols to `text`, with their breaks in front of or after them.
+    fn push_text(&self, text: &mut String, trailing_break: bool) {
+        for (i, symbol) in self.symbols.iter().enumerate() {
+            let is_prefix = symbol.detected_break().is_some_and(|b| b.is_prefix);
+            if is_prefix {
+                text.push_str(symbol.break_text());
+            }
+            text.push_str(&symbol.text);
+            if !is_prefix && (trailing_break || i + 1 < self.symbols.len()) {
+                text.push_str(symbol.break_text());
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Symbol {
-    #[serde(rename = "boundingBox")]
-    bounding_box: BoundingBox,
-    confidence: f64,
-    text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub property: Option<TextProperty>,
+    #[serde(rename = "boundingBox", default)]
+    pub bounding_box: BoundingBox,
+    #[serde(default)]
+    pub confidence: f64,
+    #[serde(default)]
+    pub text: String,
+}
+
+impl Symbol {
+    pub fn detected_break(&self) -> Option<&DetectedBreak> {
+        self.property.as_ref()?.detected_break.as_ref()
+    }
+
+    /// The text of the break detected next to the symbol, e.g. `" "` or `"\n"`.
+    pub fn break_text(&self) -> &'static str {
+        match self
+            .detected_break()
+            .map(|detected_break| detected_break.break_type)
+        {
+            Some(BreakType::Space | BreakType::SureSpace) => " ",
+            Some(BreakType::EolSureSpace | BreakType::LineBreak) => "\n",
+            Some(BreakType::Hyphen) => "-\n",
+            Some(BreakType::Unknown) | None => "",
+        }
+    }
+}
+
+/// Languages and breaks detected for a part of a [`FullTextAnnotation`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct TextProperty {
+    #[serde(rename = "detectedLanguages", skip_serializing_if = "Vec::is_empty")]
+    pub detected_languages: Vec<DetectedLanguage>,
+    #[serde(rename = "detectedBreak", skip_serializing_if = "Option::is_none")]
+    pub detected_break: Option<DetectedBreak>,
+}
+
+impl TextProperty {
+    /// The most confident of the detected languages, e.g. `en`.
+    pub fn language(&self) -> Option<&str> {
+        self.detected_languages
+            .iter()
+            .max_by(|a, b| a.confidence.total_cmp(&b.confidence))
+            .map(|language| language.language_code.as_str())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DetectedLanguage {
+    /// BCP-47 language code, e.g. `en` or `ja`.
+    #[serde(rename = "languageCode")]
+    pub language_code: String,
+    #[serde(default)]
+    pub confidence: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+pub struct DetectedBreak {
+    #[serde(rename = "type", default)]
+    pub break_type: BreakType,
+    /// The break precedes the element instead of following it.
+    #[serde(rename = "isPrefix", default)]
+    pub is_prefix: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BreakType {
+    Space,
+    /// A wide space.
+    SureSpace,
+    /// A line-wrapping space.
+    EolSureSpace,
+    /// A line-ending hyphen that is not part of the text.
+    Hyphen,
+    /// A line break that ends a paragraph.
+    LineBreak,
+    #[default]
+    #[serde(other)]
+    Unknown,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct Polygon {
+    #[serde(default)]
     pub vertices: Vec<Point>,
+    /// Vertices relative to the image size, in the range `0.0..=1.0`.
+    /// Only some annotations (e.g. localized objects) are reported this way.
+    #[serde(
+        rename = "normalizedVertices",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub normalized_vertices: Vec<NormalizedVertex>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+impl Polygon {
+    /// The axis-aligned rectangle enclosing the pixel vertices.
+    pub fn rect(&self) -> Rect {
+        enclosing_rect(&self.vertices)
+    }
+
+    /// The polygon with its normalized vertices converted to pixels of an image with the
+    /// given original dimensions, so it works with [`rect`](Self::rect) and the rest of
+    /// the crate. Polygons that already have pixel vertices are returned as they are.
+    pub fn to_pixels(&self, width: u32, height: u32) -> Polygon {
+        let mut polygon = self.clone();
+        if polygon.vertices.is_empty() {
+            polygon.vertices = self
+                .normalized_vertices
+                .iter()
+                .map(|vertex| vertex.to_point(width, height))
+                .collect();
+        }
+        polygon
+    }
+
+    /// Maps the pixel vertices with `transform`. Normalized vertices are kept as they are.
+    pub fn transform(&self, transform: &Transform) -> Polygon {
+        Polygon {
+            vertices: self
+                .vertices
+                .iter()
+                .map(|&point| transform.apply_point(point))
+                .collect(),
+            normalized_vertices: self.normalized_vertices.clone(),
+        }
+    }
+}
+
+/// The axis-aligned rectangle enclosing `points`.
+fn enclosing_rect(points: &[Point]) -> Rect {
+    let xs = points.iter().map(|point| point.x);
+    let ys = points.iter().map(|point| point.y);
+    Rect::new(
+        xs.clone().min().unwrap_or(0),
+        ys.clone().min().unwrap_or(0),
+        xs.max().unwrap_or(0),
+        ys.max().unwrap_or(0),
+    )
+}
+
+/// A vertex in pixel coordinates. The API omits coordinates that are 0, e.g. for text
+/// touching the edge of the image.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
 pub struct Point {
     pub x: i64,
     pub y: i64,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Default)]
+#[serde(default)]
+pub struct NormalizedVertex {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl NormalizedVertex {
+    /// Converts to pixel coordinates of an image with the given original dimensions.
+    pub fn to_point(&self, width: u32, height: u32) -> Point {
+        Point {
+            x: (self.x * width as f64).round() as i64,
+            y: (self.y * height as f64).round() as i64,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct BoundingBox {
-    vertices: Vec<Point>,
+    /// Four, clockwise from the top left corner of the text. Empty when a field mask
+    /// leaves them out.
+    #[serde(default)]
+    pub vertices: Vec<Point>,
 }
 
-impl BoundingBox {
-    pub fn left_top(&self) -> Point {
-        self.vertices[1]
+impl BoundingBox {
+    /// The axis-aligned rectangle enclosing all vertices.
+    pub fn rect(&self) -> Rect {
+        enclosing_rect(&self.vertices)
+    }
+
+    /// The top-left corner of [`rect`](Self::rect), whatever the order of the vertices.
+    pub fn left_top(&self) -> Point {
+        let rect = self.rect();
+        Point {
+            x: rect.left,
+            y: rect.top,
+        }
+    }
+
+    /// The bottom-right corner of [`rect`](Self::rect).
+    pub fn right_bottom(&self) -> Point {
+        let rect = self.rect();
+        Point {
+            x: rect.right,
+            y: rect.bottom,
+        }
+    }
+
+    pub fn center(&self) -> Point {
+        let rect = self.rect();
+        Point {
+            x: (rect.left + rect.right) / 2,
+            y: (rect.top + rect.bottom) / 2,
+        }
+    }
+
+    /// The width of [`rect`](Self::rect), never negative, also for rotated text.
+    pub fn width(&self) -> i64 {
+        self.rect().width()
+    }
+
+    pub fn height(&self) -> i64 {
+        self.rect().height()
+    }
+}
+
+/// The body of an `images:annotate` call.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BatchAnnotateImagesResponse {
+    /// One per image, in the order of the requests.
+    #[serde(default)]
+    pub responses: Vec<AnnotateImageResponse>,
+}
+
+impl BatchAnnotateImagesResponse {
+    /// The response for the `index`-th request.
+    pub fn get(&self, index: usize) -> Option<&AnnotateImageResponse> {
+        self.responses.get(index)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, AnnotateImageResponse> {
+        self.responses.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a BatchAnnotateImagesResponse {
+    type Item = &'a AnnotateImageResponse;
+    type IntoIter = std::slice::Iter<'a, AnnotateImageResponse>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.responses.iter()
+    }
+}
+
+impl IntoIterator for BatchAnnotateImagesResponse {
+    type Item = AnnotateImageResponse;
+    type IntoIter = std::vec::IntoIter<AnnotateImageResponse>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.responses.into_iter()
+    }
+}
+
+/// The annotations of one image, as the API returns them. Detections that weren't
+/// requested or found nothing are empty.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AnnotateImageResponse {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub text_annotations: Vec<TextAnnotation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub full_text_annotation: Option<FullTextAnnotation>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub label_annotations: Vec<EntityAnnotation>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub logo_annotations: Vec<EntityAnnotation>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub landmark_annotations: Vec<EntityAnnotation>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub face_annotations: Vec<FaceAnnotation>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub localized_object_annotations: Vec<LocalizedObjectAnnotation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub safe_search_annotation: Option<SafeSearchAnnotation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_properties_annotation: Option<ImageProperties>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crop_hints_annotation: Option<CropHintsAnnotation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub web_detection: Option<WebDetection>,
+    /// Set instead of the annotations if the API could not process the image.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ApiStatus>,
+}
+
+/// Annotations for a single image.
+pub struct Response {
+    /// The JSON the annotations were parsed from, kept for [`Response::raw`].
+    response: Value,
+    annotations: AnnotateImageResponse,
+}
+
+impl Response {
+    /// Parses the annotations in `response`, found at `pointer` in the body of the call.
+    pub(crate) fn new(response: Value, pointer: &str) -> Result<Self> {
+        Ok(Self {
+            annotations: decode::from_value(&response, pointer)?,
+            response,
+        })
+    }
+
+    /// Parses a response saved from [`Response::raw`], or an entry of the `responses` of
+    /// an `images:annotate` call, e.g. to analyze results again without calling the API.
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// let response = gcv_client::Response::from_file("photo.json")?;
+    /// for text in response.text_annotations() {
+    ///     println!("{}", text.description);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_json_str(json: &str) -> Result<Self> {
+        let mut response: Value = serde_json::from_str(json)?;
+        let error = match &mut response {
+            Value::Object(object) => object.remove("error"),
+            _ => {
+                return Err(Error::decode(format!(
+                    "response must be object: {}",
+                    response
+                )))
+            }
+        };
+        match error {
+            None | Some(Value::Null) => Self::new(response, ""),
+            Some(error) => Err(Error::Image(serde_json::from_value(error)?)),
+        }
+    }
+
+    /// Reads a response saved as JSON, see [`Response::from_json_str`].
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|error| {
+            Error::decode(
+                anyhow::Error::new(error).context(format!("failed to read {}", path.display())),
+            )
+        })?;
+
+        Self::from_json_str(&json)
+    }
+
+    pub fn annotations(&self) -> &AnnotateImageResponse {
+        &self.annotations
+    }
+
+    pub fn into_annotations(self) -> AnnotateImageResponse {
+        self.annotations
+    }
+
+    /// The JSON of the response, e.g. to read fields this crate doesn't model yet.
+    /// Geometry is already mapped like that of the annotations.
+    pub fn raw(&self) -> &Value {
+        &self.response
+    }
+
+    pub fn into_inner(self) -> Value {
+        self.response
+    }
+
+    pub fn text_annotations(&self) -> &[TextAnnotation] {
+        &self.annotations.text_annotations
+    }
+
+    /// All of the text in the image: that of `DOCUMENT_TEXT_DETECTION`, put together from
+    /// its symbols if the API left it out, or else the first annotation of
+    /// `TEXT_DETECTION`. Empty if neither found any text.
+    pub fn full_text(&self) -> String {
+        if let Some(full_text) = &self.annotations.full_text_annotation {
+            if !full_text.text.is_empty() {
+                return full_text.text.clone();
+            }
+            return full_text.pages.iter().map(Page::text).collect();
+        }
+
+        self.annotations
+            .text_annotations
+            .first()
+            .map(|text| text.description.clone())
+            .unwrap_or_default()
+    }
+
+    /// The text of `DOCUMENT_TEXT_DETECTION`, if the response has any.
+    pub fn full_text_annotations(&self) -> Option<&FullTextAnnotation> {
+        self.annotations.full_text_annotation.as_ref()
+    }
+
+    pub fn label_annotations(&self) -> &[EntityAnnotation] {
+        &self.annotations.label_annotations
+    }
+
+    pub fn logo_annotations(&self) -> &[EntityAnnotation] {
+        &self.annotations.logo_annotations
+    }
+
+    pub fn landmark_annotations(&self) -> &[EntityAnnotation] {
+        &self.annotations.landmark_annotations
+    }
+
+    pub fn face_annotations(&self) -> &[FaceAnnotation] {
+        &self.annotations.face_annotations
+    }
+
+    pub fn localized_object_annotations(&self) -> &[LocalizedObjectAnnotation] {
+        &self.annotations.localized_object_annotations
+    }
+
+    pub fn safe_search_annotation(&self) -> Option<&SafeSearchAnnotation> {
+        self.annotations.safe_search_annotation.as_ref()
+    }
+
+    pub fn image_properties(&self) -> Option<&ImageProperties> {
+        self.annotations.image_properties_annotation.as_ref()
+    }
+
+    pub fn crop_hints_annotation(&self) -> Option<&CropHintsAnnotation> {
+        self.annotations.crop_hints_annotation.as_ref()
+    }
+
+    pub fn web_detection(&self) -> Option<&WebDetection> {
+        self.annotations.web_detection.as_ref()
+    }
+
+    /// Maps every pixel coordinate in the response with `transform`, e.g. back to the
+    /// original image after it was downscaled or tiled before upload. Normalized vertices
+    /// are relative to the image and stay as they are.
+    /// ```no_run
+    /// # fn f(mut response: gcv_client::Response) -> gcv_client::Result<()> {
+    /// use gcv_client::Transform;
+    ///
+    /// // The 4000x3000 photo was uploaded at 1000x750.
+    /// response.map_geometry(&Transform::from_resize(1000, 750, 4000, 3000))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// For images encoded with [`ImageGCV`], pass [`ImageGCV::transform`].
+    pub fn map_geometry(&mut self, transform: &Transform) -> Result<()> {
+        fn map_xy(value: &mut Value, transform: &Transform, round: bool) {
+            let x = value["x"].as_f64().unwrap_or(0.0);
+            let y = value["y"].as_f64().unwrap_or(0.0);
+            let (x, y) = transform.apply(x, y);
+            if round {
+                value["x"] = (x.round() as i64).into();
+                value["y"] = (y.round() as i64).into();
+            } else {
+                value["x"] = x.into();
+                value["y"] = y.into();
+            }
+        }
+
+        fn walk(value: &mut Value, transform: &Transform) {
+            match value {
+                Value::Object(map) => {
+                    for (key, value) in map.iter_mut() {
+                        match (key.as_str(), value) {
+                            ("vertices", Value::Array(vertices)) => vertices
+                                .iter_mut()
+                                .for_each(|vertex| map_xy(vertex, transform, true)),
+                            // Face landmarks, which carry a depth as well.
+                            ("position", position @ Value::Object(_)) => {
+                                map_xy(position, transform, false)
+                            }
+                            (_, value) => walk(value, transform),
+                        }
+                    }
+                }
+                Value::Array(values) => values.iter_mut().for_each(|value| walk(value, transform)),
+                _ => {}
+            }
+        }
+
+        walk(&mut self.response, transform);
+        self.annotations = decode::from_value(&self.response, "")?;
+        Ok(())
+    }
+}
+
+/// Client for google cloud vision
+///
+/// Clones share the same connection pool. Dropping the future of a call aborts it along
+/// with its retries; see [`Client::with_cancellation`] to abort calls from elsewhere.
+#[derive(Clone)]
+pub struct Client {
+    credential: Credential,
+    http: Arc<dyn HttpTransport>,
+    endpoint: String,
+    quota_project: Option<String>,
+    /// Sent with every request, e.g. `User-Agent`.
+    headers: Vec<(String, String)>,
+    retry: Arc<dyn RetryPolicy>,
+    on_throttle: Option<Arc<dyn Fn(std::time::Duration) + Send + Sync>>,
+    on_retry: Option<retry::RetryCallback>,
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
+    cancellation: Option<CancellationToken>,
+    interceptors: Vec<Arc<dyn Interceptor>>,
+}
+
+/// Constructors that send requests with reqwest.
+#[cfg(feature = "reqwest")]
+impl Client {
+    /// Authenticates with a fixed OAuth access token. Despite the parameter name this
+    /// is not an API key; use [`Client::from_api_key`] for those.
+    pub fn new(apikey: &str) -> Self {
+        Self::with_credential(Credential::access_token(apikey))
+    }
+
+    pub fn with_credential(credential: Credential) -> Self {
+        Self::with_transport(Arc::new(reqwest::Client::new()), credential)
+    }
+
+    /// Sends requests with `http`, e.g. a client configured with a corporate proxy,
+    /// custom root certificates or connection limits.
+    pub fn with_http_client(http: reqwest::Client, credential: Credential) -> Self {
+        Self::with_transport(Arc::new(http), credential)
+    }
+
+    /// Authenticates with an API key from the Cloud Console.
+    pub fn from_api_key(key: &str) -> Self {
+        Self::with_credential(Credential::ApiKey(key.to_string()))
+    }
+
+    /// Authenticates as a service account, fetching and refreshing access tokens as needed.
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// let service_account = gcv_client::ServiceAccount::from_file("/path/to/key.json")?;
+    /// let client = gcv_client::Client::from_service_account(service_account);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_service_account(service_account: ServiceAccount) -> Self {
+        Self::from_token_provider(Arc::new(service_account))
+    }
+
+    /// Authenticates with tokens from the metadata server, e.g. on GKE with Workload Identity.
+    pub fn from_metadata_server(metadata: MetadataServer) -> Self {
+        Self::from_token_provider(Arc::new(metadata))
+    }
+
+    /// Authenticates as `target` by impersonating it with the tokens of `source`, for
+    /// environments where service account keys may not be exported.
+    pub fn from_impersonation(source: Arc<dyn TokenProvider>, target: &str) -> Self {
+        Self::from_token_provider(Arc::new(ImpersonatedServiceAccount::new(source, target)))
+    }
+
+    /// Authenticates with tokens from a custom source.
+    pub fn from_token_provider(tokens: Arc<dyn TokenProvider>) -> Self {
+        Self::with_credential(Credential::Bearer(tokens))
+    }
+
+    /// Uses Application Default Credentials, looked up in the same order as the Google
+    /// Cloud SDKs: the key file named by `GOOGLE_APPLICATION_CREDENTIALS`, the user
+    /// credentials from `gcloud auth application-default login`, then the metadata
+    /// server when running on Google Cloud.
+    pub async fn new_adc() -> Result<Self> {
+        Ok(Self::from_token_provider(
+            auth::application_default().await.map_err(Error::auth)?,
+        ))
+    }
+
+    /// Uses Application Default Credentials as resolved by the gcp_auth crate.
+    #[cfg(feature = "gcp-auth")]
+    pub async fn new_gcp_auth() -> Result<Self> {
+        Ok(Self::from_token_provider(Arc::new(GcpAuth::new().await?)))
+    }
+
+    /// The token is used as is and is not refreshed, so this suits short-lived
+    /// processes; long-running jobs should use [`Client::new_adc`] instead.
+    /// The most commonly used methods are
+    /// ```bash
+    /// export GOOGLE_APPLICATION_CREDENTIALS=/path/to/key.json
+    /// export GCV_API_KEY=`gcloud auth application-default print-access-token`
+    /// ```
+    pub fn new_from_env() -> Option<Self> {
+        Some(Self::new(std::env::var("GCV_API_KEY").ok()?.as_str()))
+    }
+}
+
+impl Client {
+    /// Sends requests through another HTTP stack than reqwest, or a test double.
+    pub fn with_transport(transport: Arc<dyn HttpTransport>, credential: Credential) -> Self {
+        credential.use_transport(&transport);
+        Self {
+            credential,
+            http: transport,
+            endpoint: DEFAULT_ENDPOINT.to_string(),
+            quota_project: None,
+            headers: vec![],
+            retry: Arc::new(NoRetry),
+            on_throttle: None,
+            on_retry: None,
+            circuit_breaker: None,
+            cancellation: None,
+            interceptors: vec![],
+        }
+    }
+
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+
+    /// A client that sends its requests with `credential` but shares the connection pool
+    /// of `self`, e.g. to bill each tenant's project in a multi-tenant service.
+    pub fn scoped(&self, credential: Credential) -> Self {
+        credential.use_transport(&self.http);
+        Self {
+            credential,
+            ..self.clone()
+        }
+    }
+
+    /// A client whose calls, including retries, fail once `token` is cancelled, e.g.
+    /// when the user leaves the screen that started them. Shares the connection pool of
+    /// `self`.
+    pub fn with_cancellation(&self, token: CancellationToken) -> Self {
+        Self {
+            cancellation: Some(token),
+            ..self.clone()
+        }
     }
-    pub fn width(&self) -> i64 {
-        &self.vertices[3].x - &self.vertices[1].x
+
+    /// Runs document OCR (`DOCUMENT_TEXT_DETECTION`) on the image.
+    /// Use [`Client::request_with_features`] to choose other detections.
+    pub async fn request(&self, image: &ImageGCV) -> Result<Response> {
+        self.request_with_features(image, &[Feature::DocumentTextDetection])
+            .await
     }
 
-    pub fn height(&self) -> i64 {
-        &self.vertices[3].y - &self.vertices[1].y
+    pub async fn request_with_features(
+        &self,
+        image: &ImageGCV,
+        features: &[Feature],
+    ) -> Result<Response> {
+        let request = AnnotateRequestBuilder::new(image)
+            .features(features.iter().copied())
+            .build();
+
+        self.annotate(&request).await
     }
-}
 
-pub struct Response {
-    response: Value,
-}
+    /// Runs document OCR on `region` of `image`, uploading only that part.
+    /// Pixel coordinates in the response are offset back into full image coordinates.
+    pub async fn request_region(&self, image: &DynamicImage, region: Rect) -> Result<Response> {
+        let bounds = Rect::new(0, 0, image.width() as i64, image.height() as i64);
+        let region = region.intersection(&bounds);
+        if region.is_empty() {
+            return Err(Error::InvalidInput(
+                "region does not overlap the image".to_string(),
+            ));
+        }
 
-impl Response {
-    pub fn text_annotations(&self) -> anyhow::Result<Vec<TextAnnotation>> {
-        let json_response = &self.response;
-        let text_annotations_value = &json_response["responses"][0]["textAnnotations"];
+        let cropped = image.crop_imm(
+            region.left as u32,
+            region.top as u32,
+            region.width() as u32,
+            region.height() as u32,
+        );
+        let mut response = self.request(&ImageGCV::from_image(&cropped)?).await?;
+        response.map_geometry(&Transform::translate(region.left as f64, region.top as f64))?;
+
+        Ok(response)
+    }
 
-        Ok(text_annotations_value
-            .as_array()
-            .with_context(|| format!("text_annotations must be array: {}", json_response))?
+    /// Runs document OCR on up to [`MAX_BATCH_SIZE`] images in a single call.
+    /// The responses are in the same order as `images`.
+    pub async fn request_batch(
+        &self,
+        images: &[ImageGCV],
+    ) -> Result<Vec<Result<Response, ApiStatus>>> {
+        let requests: Vec<_> = images
             .iter()
-            .map(|x| {
-                serde_json::from_value(x.clone()).expect("textAnnotation json value parse error")
+            .map(|image| {
+                AnnotateRequestBuilder::new(image)
+                    .feature(Feature::DocumentTextDetection)
+                    .build()
             })
-            .collect())
+            .collect();
+
+        self.annotate_batch(&requests).await
     }
 
-    pub fn full_text_annotations(&self) -> anyhow::Result<FullTextAnnotation> {
-        let json_response = &self.response;
-        let full_text_annotations_value = &json_response["responses"][0]["fullTextAnnotation"];
+    pub async fn annotate(&self, request: &AnnotateImageRequest) -> Result<Response> {
+        let mut responses = self.annotate_batch(std::slice::from_ref(request)).await?;
 
-        Ok(serde_json::from_value(full_text_annotations_value.clone())?)
+        responses.remove(0).map_err(Error::Image)
     }
-}
 
-/// Client for google cloud vision
-pub struct Client {
-    credential: String,
-}
+    /// Sends up to [`MAX_BATCH_SIZE`] requests in a single call.
+    /// The responses are in the same order as `requests`, each with the error of its
+    /// image if the API could not process it.
+    pub async fn annotate_batch(
+        &self,
+        requests: &[AnnotateImageRequest],
+    ) -> Result<Vec<Result<Response, ApiStatus>>> {
+        if requests.len() > MAX_BATCH_SIZE {
+            return Err(Error::InvalidInput(format!(
+                "at most {} images can be annotated in one call, got {}",
+                MAX_BATCH_SIZE,
+                requests.len()
+            )));
+        }
 
-impl Client {
-    pub fn new(apikey: &str) -> Self {
-        Self {
-            credential: apikey.to_string(),
+        if requests.is_empty() {
+            return Ok(vec![]);
+        }
+
+        check_request_size(requests)?;
+
+        let request = request::BatchAnnotateImagesRequest { requests };
+
+        let call = async {
+            match request.deadline() {
+                Some(deadline) => tokio::time::timeout(deadline, self.call(&request))
+                    .await
+                    .map_err(|_| Error::Timeout)?,
+                None => self.call(&request).await,
+            }
+        };
+        let mut response = match &self.cancellation {
+            Some(token) => tokio::select! {
+                biased;
+                _ = token.cancelled() => return Err(Error::Cancelled),
+                response = call => response?,
+            },
+            None => call.await?,
+        };
+
+        if !(200..300).contains(&response.status) {
+            return Err(status_error(&response));
+        }
+        let json_response = decode::parse_body(&mut response.body)?;
+
+        split_responses(json_response, requests.len())
+    }
+
+    async fn call(
+        &self,
+        request: &request::BatchAnnotateImagesRequest<'_>,
+    ) -> Result<HttpResponse> {
+        let response = self.send_with_retry(request).await?;
+        // A token can be revoked before it expires; fetch a new one and try once more.
+        if response.status == 401 && self.credential.invalidate().await {
+            return self.send_with_retry(request).await;
         }
+
+        Ok(response)
     }
 
-    /// The most commonly used methods are
-    /// ```bash
-    /// export GOOGLE_APPLICATION_CREDENTIALS=/path/to/key.json
-    /// export GCV_API_KEY=`gcloud auth application-default print-access-token`
-    /// ```
-    pub fn new_from_env() -> Option<Self> {
-        Some(Self::new(std::env::var("GCV_API_KEY").ok()?.as_str()))
+    async fn send_with_retry(
+        &self,
+        request: &request::BatchAnnotateImagesRequest<'_>,
+    ) -> Result<HttpResponse> {
+        let mut retry = 0;
+        loop {
+            if let Some(breaker) = &self.circuit_breaker {
+                breaker.acquire()?;
+            }
+            let result = self.send(request).await;
+            let outcome = match &result {
+                Ok(response) if response.status < 400 => None,
+                Ok(response) => Some(retry::Outcome::Response(response)),
+                Err(error) => Some(retry::Outcome::Error(error)),
+            };
+            if let Some(breaker) = &self.circuit_breaker {
+                breaker.record(outcome);
+            }
+
+            let Some(outcome) = outcome else {
+                return result;
+            };
+            let Some(delay) = self.retry.retry(retry, outcome) else {
+                return result;
+            };
+
+            if let (Some(retry_after), Some(on_throttle)) =
+                (outcome.retry_after(), &self.on_throttle)
+            {
+                on_throttle(retry_after);
+            }
+            retry += 1;
+            if let Some(on_retry) = &self.on_retry {
+                on_retry(&retry::RetryEvent {
+                    attempt: retry,
+                    outcome,
+                    delay,
+                });
+            }
+            tokio::time::sleep(delay).await;
+        }
     }
 
-    pub async fn request(&self, image: &ImageGCV) -> anyhow::Result<Response> {
-        let request = json!({
-           "requests" : [
-               {
-                    "image": {
-                        "content": image.base64_data
-                    },
-                    "features": [
-                        {
-                            "type": "DOCUMENT_TEXT_DETECTION"
-                        }
-                    ],
-               }
-           ]
-        });
+    async fn send(
+        &self,
+        request: &request::BatchAnnotateImagesRequest<'_>,
+    ) -> Result<HttpResponse> {
+        let mut url = url::Url::parse(&format!("{}/v1/images:annotate", self.endpoint))
+            .map_err(|error| Error::InvalidInput(format!("invalid endpoint: {}", error)))?;
+        if let Some(fields) = request.fields() {
+            url.query_pairs_mut().append_pair("fields", &fields);
+        }
+        let mut http_request =
+            HttpRequest::post_json(url, serde_json::to_vec(request).map_err(Error::encode)?);
+        http_request.headers.extend(self.headers.iter().cloned());
+        if let Some(project) = &self.quota_project {
+            http_request
+                .headers
+                .push(("x-goog-user-project".to_string(), project.clone()));
+        }
+        self.credential.authorize(&mut http_request).await?;
+        for interceptor in &self.interceptors {
+            interceptor.before_send(&mut http_request).await?;
+        }
+
+        let response = self.http.send(http_request).await;
+        for interceptor in self.interceptors.iter().rev() {
+            interceptor.after_response(response.as_ref()).await;
+        }
 
-        let response = reqwest::Client::new()
-            .post(CLOUD_VISION_URI)
-            .header("Authorization", format!("Bearer {}", self.credential))
-            .json(&request)
-            .send()
-            .await?;
+        response
+    }
+}
 
-        let json_response: Value = response.json().await?;
+/// The error for a failed `response`, e.g. a 502 page of a proxy, which may carry an
+/// error object of the API.
+fn status_error(response: &HttpResponse) -> Error {
+    let error = serde_json::from_slice::<Value>(&response.body)
+        .ok()
+        .and_then(|mut json| serde_json::from_value::<GoogleApiError>(json["error"].take()).ok());
+    let failure = Box::new(HttpFailure::new(response));
 
-        let err = &json_response["error"];
+    if response.status == 429 || error.as_ref().is_some_and(|e| e.is_quota_exceeded()) {
+        return Error::QuotaExceeded {
+            quota: Box::new(QuotaViolation::new(error.as_ref(), response)),
+            error,
+            response: failure,
+        };
+    }
+    match error {
+        Some(error) => Error::Api {
+            error,
+            response: failure,
+        },
+        None => Error::Status(failure),
+    }
+}
 
-        if err.is_object() {
-            return Err(anyhow::anyhow!(err.to_string()));
+/// Splits the body of an `images:annotate` call into one [`Response`] or error per image.
+fn split_responses(
+    mut json_response: Value,
+    expected: usize,
+) -> Result<Vec<Result<Response, ApiStatus>>> {
+    let responses = match json_response.get_mut("responses").map(Value::take) {
+        Some(Value::Array(responses)) => responses,
+        _ => {
+            return Err(Error::decode(format!(
+                "responses must be array: {}",
+                json_response
+            )))
         }
+    };
 
-        Ok(Response {
-            response: json_response,
-        })
+    if responses.len() != expected {
+        return Err(Error::decode(format!(
+            "expected {} responses, got {}",
+            expected,
+            responses.len()
+        )));
     }
+
+    responses
+        .into_iter()
+        .enumerate()
+        .map(|(i, mut response)| {
+            let error = match &mut response {
+                Value::Object(object) => object.remove("error"),
+                _ => {
+                    return Err(Error::decode(format!(
+                        "response {} must be object: {}",
+                        i, response
+                    )))
+                }
+            };
+            match error {
+                None | Some(Value::Null) => {
+                    Ok(Ok(Response::new(response, &format!("/responses/{}", i))?))
+                }
+                Some(error) => Ok(Err(serde_json::from_value(error)?)),
+            }
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{Client, ImageGCV, TextAnnotation};
+    use crate::test_fixtures::{full_text, full_text_json, word};
+    #[cfg(feature = "reqwest")]
+    use crate::test_server::{image_request, TestServer};
+    use crate::{
+        AnnotateRequestBuilder, BoundingBox, EncodeAs, EncodeOptions, Feature, ImageGCV,
+        ImageSource, LatLng, Likelihood, Point, Rect, Response, TextAnnotation, Transform, Word,
+    };
+    #[cfg(feature = "reqwest")]
+    use crate::{CancellationToken, Client};
     use image::io::Reader as ImageReader;
-    use serde_json::Value;
+    use image::{DynamicImage, ImageFormat};
+    use serde_json::{json, Value};
 
+    #[cfg(feature = "reqwest")]
     #[tokio::test]
     async fn it_works() {
         let client = Client::new(
@@ -211,7 +1667,7 @@ mod tests {
                 .expect("please set GCV_API_KEY")
                 .as_str(),
         );
-        let mut image = ImageReader::open("test/test.png")
+        let image = ImageReader::open("test/test.png")
             .unwrap()
             .decode()
             .unwrap();
@@ -219,14 +1675,15 @@ mod tests {
         let resp = client.request(&gcv_image).await;
         assert!(resp.is_ok());
 
-        let result = resp.unwrap().text_annotations().unwrap();
+        let response = resp.unwrap();
+        let result = response.text_annotations();
 
         assert_eq!(result.len(), 2);
         assert_eq!(result[0].description, "44097050");
     }
     #[test]
     fn encode_() {
-        let mut image = ImageReader::open("test/test10.png")
+        let image = ImageReader::open("test/test10.png")
             .unwrap()
             .decode()
             .unwrap();
@@ -235,6 +1692,119 @@ mod tests {
 
         assert_ne!(gcv_image.base64_data.len(), 0);
     }
+
+    #[test]
+    fn from_image_as_jpeg() {
+        let image = ImageReader::open("test/test10.png")
+            .unwrap()
+            .decode()
+            .unwrap();
+        let gcv_image = ImageGCV::from_image_as(&image, EncodeAs::Jpeg { quality: 85 }).unwrap();
+        let bytes = base64::decode(&gcv_image.base64_data).unwrap();
+
+        assert_eq!(image::guess_format(&bytes).unwrap(), ImageFormat::Jpeg);
+        assert!(ImageGCV::from_image_as(&image, EncodeAs::Jpeg { quality: 0 }).is_err());
+    }
+
+    #[test]
+    fn from_image_with_max_edge() {
+        let image = DynamicImage::new_rgb8(400, 100);
+        let options = EncodeOptions {
+            max_edge: Some(100),
+            ..Default::default()
+        };
+
+        let gcv_image = ImageGCV::from_image_with(&image, &options).unwrap();
+        let bytes = base64::decode(&gcv_image.base64_data).unwrap();
+        let uploaded = image::load_from_memory(&bytes).unwrap();
+
+        assert_eq!((uploaded.width(), uploaded.height()), (100, 25));
+        assert_eq!(gcv_image.transform().apply(100.0, 25.0), (400.0, 100.0));
+
+        let small = DynamicImage::new_rgb8(50, 20);
+        let gcv_image = ImageGCV::from_image_with(&small, &options).unwrap();
+        assert_eq!(*gcv_image.transform(), Transform::IDENTITY);
+    }
+
+    #[test]
+    fn size_limits() {
+        assert!(crate::check_image_size(crate::MAX_IMAGE_SIZE).is_ok());
+        let err = crate::check_image_size(12 * 1024 * 1024).unwrap_err();
+        assert!(err.to_string().contains("12.0 MB"));
+
+        let request = |size| {
+            AnnotateRequestBuilder::new(ImageSource::Content("A".repeat(size)))
+                .feature(Feature::DocumentTextDetection)
+                .build()
+        };
+        let half = crate::MAX_REQUEST_SIZE / 2;
+        assert!(crate::check_request_size(&[request(half), request(half)]).is_ok());
+        assert!(crate::check_request_size(&[request(half), request(half + 1)]).is_err());
+    }
+
+    /// Encodes a JPEG with an EXIF APP1 segment holding only the orientation tag.
+    fn jpeg_with_orientation(image: &DynamicImage, orientation: u16) -> Vec<u8> {
+        let mut jpeg = vec![];
+        image
+            .write_to(&mut std::io::Cursor::new(&mut jpeg), ImageFormat::Jpeg)
+            .unwrap();
+
+        let mut app1 = b"Exif\0\0II*\0\x08\0\0\0\x01\0\x12\x01\x03\0\x01\0\0\0".to_vec();
+        app1.extend_from_slice(&orientation.to_le_bytes());
+        app1.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+
+        let mut bytes = vec![0xff, 0xd8, 0xff, 0xe1];
+        bytes.extend_from_slice(&(app1.len() as u16 + 2).to_be_bytes());
+        bytes.extend_from_slice(&app1);
+        bytes.extend_from_slice(&jpeg[2..]);
+        bytes
+    }
+
+    #[test]
+    fn apply_exif_orientation() {
+        let bytes = jpeg_with_orientation(&DynamicImage::new_rgb8(40, 20), 6);
+        let options = EncodeOptions {
+            apply_exif_orientation: true,
+            ..Default::default()
+        };
+
+        let gcv_image = ImageGCV::from_bytes_with(&bytes, &options).unwrap();
+        let uploaded =
+            image::load_from_memory(&base64::decode(&gcv_image.base64_data).unwrap()).unwrap();
+
+        assert_eq!((uploaded.width(), uploaded.height()), (20, 40));
+        assert_eq!(gcv_image.transform().apply(0.0, 0.0), (0.0, 20.0));
+
+        let gcv_image = ImageGCV::from_bytes_with(&bytes, &EncodeOptions::default()).unwrap();
+        assert_eq!(gcv_image.base64_data, base64::encode(&bytes));
+    }
+
+    #[test]
+    fn from_encoded_bytes() {
+        let png = std::fs::read("test/test.png").unwrap();
+        let gcv_image = ImageGCV::from_encoded_bytes(&png).unwrap();
+        assert_eq!(gcv_image.base64_data, base64::encode(&png));
+
+        let mut jpeg = vec![];
+        DynamicImage::new_rgb8(8, 8)
+            .write_to(&mut std::io::Cursor::new(&mut jpeg), ImageFormat::Jpeg)
+            .unwrap();
+        let gcv_image = ImageGCV::from_encoded_bytes(&jpeg).unwrap();
+        assert_eq!(gcv_image.base64_data, base64::encode(&jpeg));
+
+        assert!(ImageGCV::from_encoded_bytes(b"not an image").is_err());
+    }
+
+    #[test]
+    fn from_path() {
+        let gcv_image = ImageGCV::from_path("test/test.png").unwrap();
+
+        assert_eq!(
+            gcv_image.base64_data,
+            base64::encode(std::fs::read("test/test.png").unwrap())
+        );
+        assert!(ImageGCV::from_path("test/missing.png").is_err());
+    }
     #[test]
     fn deserialize_text_annotation() {
         let json_text = r#"{
@@ -265,11 +1835,25 @@ mod tests {
 
         assert_eq!(text_annotation.description, "ENGINE");
         assert_eq!(text_annotation.locale, None);
+        assert_eq!(text_annotation.confidence, None);
         assert_eq!(text_annotation.bounding_poly.vertices.len(), 4);
         assert_eq!(text_annotation.bounding_poly.vertices[0].x, 1222);
         assert_eq!(text_annotation.bounding_poly.vertices[0].y, 1771);
     }
 
+    #[test]
+    fn deserialize_text_annotation_confidence() {
+        let text_annotation: TextAnnotation = serde_json::from_value(json!({
+            "description": "EXIT",
+            "confidence": 0.93,
+            "boundingPoly": { "vertices": [] }
+        }))
+        .unwrap();
+
+        assert_eq!(text_annotation.confidence, Some(0.93));
+    }
+
+    #[cfg(feature = "reqwest")]
     #[tokio::test]
     async fn full_text_annotation() {
         let client = Client::new(
@@ -277,7 +1861,7 @@ mod tests {
                 .expect("please set GCV_API_KEY")
                 .as_str(),
         );
-        let mut image = ImageReader::open("test/test.png")
+        let image = ImageReader::open("test/test.png")
             .unwrap()
             .decode()
             .unwrap();
@@ -285,8 +1869,797 @@ mod tests {
         let resp = client.request(&gcv_image).await;
         assert!(resp.is_ok());
 
-        let result = resp.unwrap().full_text_annotations().unwrap();
+        let response = resp.unwrap();
+        let result = response.full_text_annotations().unwrap();
 
         dbg!(result);
     }
+
+    #[test]
+    fn label_annotations() {
+        let response = Response::new(
+            json!({
+                "labelAnnotations": [
+                    {
+                        "mid": "/m/01g317",
+                        "description": "Font",
+                        "score": 0.9641,
+                        "topicality": 0.9641
+                    },
+                    {
+                        "mid": "/m/03scnj",
+                        "description": "Line",
+                        "score": 0.8267,
+                        "topicality": 0.7
+                    }
+                ]
+            }),
+            "",
+        )
+        .unwrap();
+
+        let labels = response.label_annotations();
+
+        assert_eq!(labels.len(), 2);
+        assert_eq!(labels[0].mid.as_deref(), Some("/m/01g317"));
+        assert_eq!(labels[0].description, "Font");
+        assert_eq!(labels[1].score, 0.8267);
+        assert_eq!(labels[1].topicality, 0.7);
+    }
+
+    #[test]
+    fn missing_annotations_are_empty() {
+        let response = Response::new(json!({}), "").unwrap();
+
+        assert!(response.label_annotations().is_empty());
+    }
+
+    #[test]
+    fn face_annotations() {
+        let response = Response::new(
+            json!({
+                "faceAnnotations": [
+                    {
+                        "boundingPoly": {
+                            "vertices": [
+                                { "x": 10, "y": 20 },
+                                { "x": 110, "y": 20 },
+                                { "x": 110, "y": 140 },
+                                { "x": 10, "y": 140 }
+                            ]
+                        },
+                        "fdBoundingPoly": {
+                            "vertices": [
+                                { "x": 20, "y": 40 },
+                                { "x": 100, "y": 40 },
+                                { "x": 100, "y": 130 },
+                                { "x": 20, "y": 130 }
+                            ]
+                        },
+                        "landmarks": [
+                            {
+                                "type": "LEFT_EYE",
+                                "position": { "x": 45.5, "y": 70.25, "z": -0.5 }
+                            }
+                        ],
+                        "rollAngle": 1.5,
+                        "detectionConfidence": 0.98,
+                        "joyLikelihood": "VERY_LIKELY",
+                        "sorrowLikelihood": "VERY_UNLIKELY",
+                        "angerLikelihood": "UNLIKELY",
+                        "surpriseLikelihood": "POSSIBLE"
+                    }
+                ]
+            }),
+            "",
+        )
+        .unwrap();
+
+        let faces = response.face_annotations();
+
+        assert_eq!(faces.len(), 1);
+        let face = &faces[0];
+        assert_eq!(face.bounding_poly.vertices[2].x, 110);
+        assert_eq!(face.fd_bounding_poly.vertices[0].y, 40);
+        assert_eq!(face.landmarks[0].landmark_type, "LEFT_EYE");
+        assert_eq!(face.landmarks[0].position.y, 70.25);
+        assert_eq!(face.roll_angle, 1.5);
+        assert_eq!(face.joy_likelihood, Likelihood::VeryLikely);
+        assert_eq!(face.sorrow_likelihood, Likelihood::VeryUnlikely);
+        assert_eq!(face.anger_likelihood, Likelihood::Unlikely);
+        assert_eq!(face.surprise_likelihood, Likelihood::Possible);
+        assert_eq!(face.headwear_likelihood, Likelihood::Unknown);
+        assert!(face.joy_likelihood >= Likelihood::Likely);
+    }
+
+    #[test]
+    fn localized_object_annotations() {
+        let response = Response::new(
+            json!({
+                "localizedObjectAnnotations": [
+                    {
+                        "mid": "/m/01bqk0",
+                        "name": "Bicycle wheel",
+                        "score": 0.89,
+                        "boundingPoly": {
+                            "normalizedVertices": [
+                                { "x": 0.25, "y": 0.5 },
+                                { "x": 0.75, "y": 0.5 },
+                                { "x": 0.75, "y": 1.0 },
+                                { "y": 1.0 }
+                            ]
+                        }
+                    }
+                ]
+            }),
+            "",
+        )
+        .unwrap();
+
+        let objects = response.localized_object_annotations();
+
+        assert_eq!(objects.len(), 1);
+        assert_eq!(objects[0].name, "Bicycle wheel");
+        let poly = &objects[0].bounding_poly;
+        assert!(poly.vertices.is_empty());
+        assert_eq!(poly.normalized_vertices.len(), 4);
+        assert_eq!(poly.normalized_vertices[3].x, 0.0);
+
+        let p = poly.normalized_vertices[1].to_point(200, 100);
+        assert_eq!((p.x, p.y), (150, 50));
+        assert_eq!(poly.to_pixels(200, 100).rect(), Rect::new(0, 50, 150, 100));
+    }
+
+    #[test]
+    fn safe_search_annotation() {
+        let response = Response::new(
+            json!({
+                "safeSearchAnnotation": {
+                    "adult": "VERY_UNLIKELY",
+                    "spoof": "UNLIKELY",
+                    "medical": "POSSIBLE",
+                    "violence": "LIKELY",
+                    "racy": "VERY_LIKELY"
+                }
+            }),
+            "",
+        )
+        .unwrap();
+
+        let safe_search = response.safe_search_annotation().unwrap();
+
+        assert_eq!(safe_search.adult, Likelihood::VeryUnlikely);
+        assert_eq!(safe_search.spoof, Likelihood::Unlikely);
+        assert_eq!(safe_search.medical, Likelihood::Possible);
+        assert_eq!(safe_search.violence, Likelihood::Likely);
+        assert_eq!(safe_search.racy, Likelihood::VeryLikely);
+
+        let response = Response::new(json!({}), "").unwrap();
+        assert!(response.safe_search_annotation().is_none());
+    }
+
+    #[test]
+    fn image_properties() {
+        let response = Response::new(
+            json!({
+                "imagePropertiesAnnotation": {
+                    "dominantColors": {
+                        "colors": [
+                            {
+                                "color": { "red": 250, "green": 120.4 },
+                                "score": 0.6,
+                                "pixelFraction": 0.35
+                            },
+                            {
+                                "color": { "red": 12, "green": 34, "blue": 56 },
+                                "score": 0.1,
+                                "pixelFraction": 0.02
+                            }
+                        ]
+                    }
+                }
+            }),
+            "",
+        )
+        .unwrap();
+
+        let properties = response.image_properties().unwrap();
+        let colors = &properties.dominant_colors.colors;
+
+        assert_eq!(colors.len(), 2);
+        assert_eq!(colors[0].color.to_rgb(), [250, 120, 0]);
+        assert_eq!(colors[0].score, 0.6);
+        assert_eq!(colors[0].pixel_fraction, 0.35);
+        assert_eq!(colors[1].color.to_rgb(), [12, 34, 56]);
+    }
+
+    #[test]
+    fn crop_hints_annotation() {
+        let response = Response::new(
+            json!({
+                "cropHintsAnnotation": {
+                    "cropHints": [
+                        {
+                            "boundingPoly": {
+                                "vertices": [
+                                    { "x": 0, "y": 0 },
+                                    { "x": 640, "y": 0 },
+                                    { "x": 640, "y": 360 },
+                                    { "x": 0, "y": 360 }
+                                ]
+                            },
+                            "confidence": 0.8,
+                            "importanceFraction": 0.95
+                        }
+                    ]
+                }
+            }),
+            "",
+        )
+        .unwrap();
+
+        let crop_hints = response.crop_hints_annotation().unwrap();
+
+        assert_eq!(crop_hints.crop_hints.len(), 1);
+        let hint = &crop_hints.crop_hints[0];
+        assert_eq!(hint.bounding_poly.vertices[2].x, 640);
+        assert_eq!(hint.confidence, 0.8);
+        assert_eq!(hint.importance_fraction, 0.95);
+    }
+
+    #[test]
+    fn web_detection() {
+        let response = Response::new(
+            json!({
+                "webDetection": {
+                    "webEntities": [
+                        { "entityId": "/m/0bt9lr", "score": 1.2, "description": "Dog" }
+                    ],
+                    "fullMatchingImages": [
+                        { "url": "https://example.com/dog.jpg" }
+                    ],
+                    "pagesWithMatchingImages": [
+                        {
+                            "url": "https://example.com/dogs",
+                            "pageTitle": "Dogs",
+                            "partialMatchingImages": [
+                                { "url": "https://example.com/dog_small.jpg" }
+                            ]
+                        }
+                    ],
+                    "visuallySimilarImages": [
+                        { "url": "https://example.com/other_dog.jpg" }
+                    ],
+                    "bestGuessLabels": [
+                        { "label": "dog", "languageCode": "en" }
+                    ]
+                }
+            }),
+            "",
+        )
+        .unwrap();
+
+        let web = response.web_detection().unwrap();
+
+        assert_eq!(web.web_entities[0].description.as_deref(), Some("Dog"));
+        assert_eq!(web.web_entities[0].score, 1.2);
+        assert_eq!(
+            web.full_matching_images[0].url,
+            "https://example.com/dog.jpg"
+        );
+        assert!(web.partial_matching_images.is_empty());
+        assert_eq!(
+            web.pages_with_matching_images[0].page_title.as_deref(),
+            Some("Dogs")
+        );
+        assert_eq!(
+            web.pages_with_matching_images[0]
+                .partial_matching_images
+                .len(),
+            1
+        );
+        assert_eq!(web.visually_similar_images.len(), 1);
+        assert_eq!(web.best_guess_labels[0].label, "dog");
+    }
+
+    #[test]
+    fn logo_annotations() {
+        let response = Response::new(
+            json!({
+                "logoAnnotations": [
+                    {
+                        "mid": "/m/045c7b",
+                        "description": "Google",
+                        "score": 0.97,
+                        "boundingPoly": {
+                            "vertices": [
+                                { "x": 10, "y": 10 },
+                                { "x": 90, "y": 10 },
+                                { "x": 90, "y": 40 },
+                                { "x": 10, "y": 40 }
+                            ]
+                        }
+                    }
+                ]
+            }),
+            "",
+        )
+        .unwrap();
+
+        let logos = response.logo_annotations();
+
+        assert_eq!(logos.len(), 1);
+        assert_eq!(logos[0].description, "Google");
+        assert_eq!(logos[0].score, 0.97);
+        assert_eq!(logos[0].bounding_poly.as_ref().unwrap().vertices[1].x, 90);
+    }
+
+    #[test]
+    fn landmark_annotations() {
+        let response = Response::new(
+            json!({
+                "landmarkAnnotations": [
+                    {
+                        "mid": "/m/0b__kbm",
+                        "description": "Tokyo Tower",
+                        "score": 0.88,
+                        "boundingPoly": {
+                            "vertices": [
+                                { "x": 100, "y": 20 },
+                                { "x": 300, "y": 20 },
+                                { "x": 300, "y": 600 },
+                                { "x": 100, "y": 600 }
+                            ]
+                        },
+                        "locations": [
+                            {
+                                "latLng": {
+                                    "latitude": 35.6585805,
+                                    "longitude": 139.7454329
+                                }
+                            }
+                        ]
+                    }
+                ]
+            }),
+            "",
+        )
+        .unwrap();
+
+        let landmarks = response.landmark_annotations();
+
+        assert_eq!(landmarks.len(), 1);
+        assert_eq!(landmarks[0].description, "Tokyo Tower");
+        assert_eq!(
+            landmarks[0].locations[0].lat_lng,
+            LatLng {
+                latitude: 35.6585805,
+                longitude: 139.7454329
+            }
+        );
+    }
+
+    #[test]
+    fn split_responses() {
+        let responses = crate::split_responses(
+            json!({
+                "responses": [
+                    { "labelAnnotations": [{ "description": "Cat", "score": 0.9 }] },
+                    {},
+                    { "labelAnnotations": [{ "description": "Dog", "score": 0.8 }] },
+                    { "error": { "code": 3, "message": "Bad image data." } }
+                ]
+            }),
+            4,
+        )
+        .unwrap();
+
+        assert_eq!(responses.len(), 4);
+        let ok: Vec<_> = responses[..3].iter().map(|r| r.as_ref().unwrap()).collect();
+        assert_eq!(ok[0].label_annotations()[0].description, "Cat");
+        assert!(ok[1].label_annotations().is_empty());
+        assert_eq!(ok[2].label_annotations()[0].description, "Dog");
+        let error = responses[3].as_ref().err().unwrap();
+        assert_eq!(error.code, 3);
+        assert_eq!(error.message, "Bad image data.");
+
+        assert!(crate::split_responses(json!({ "responses": [{}] }), 2).is_err());
+        assert!(crate::split_responses(json!({}), 1).is_err());
+        // e.g. from a proxy
+        assert!(crate::split_responses(json!([]), 0).is_err());
+        assert!(crate::split_responses(json!({ "responses": [1] }), 1).is_err());
+
+        let error = crate::split_responses(
+            json!({ "responses": [{ "labelAnnotations": [{ "description": "Cat", "score": "high" }] }] }),
+            1,
+        )
+        .err()
+        .unwrap();
+        let crate::Error::Decode(source) = error else {
+            panic!("expected a decode error, got {:?}", error);
+        };
+        assert_eq!(
+            source
+                .downcast_ref::<crate::SchemaMismatch>()
+                .unwrap()
+                .pointer,
+            "/responses/0/labelAnnotations/0/score"
+        );
+    }
+
+    #[test]
+    fn response_from_json() {
+        let response = Response::from_json_str(
+            r#"{"labelAnnotations": [{"description": "Cat", "score": 0.9}], "newField": 1}"#,
+        )
+        .unwrap();
+        assert_eq!(response.label_annotations()[0].description, "Cat");
+        assert_eq!(response.raw()["newField"], 1);
+
+        let error =
+            Response::from_json_str(r#"{"error": {"code": 3, "message": "Bad image data."}}"#)
+                .err()
+                .unwrap();
+        assert!(matches!(error, crate::Error::Image(status) if status.code == 3));
+        assert!(matches!(
+            Response::from_json_str("[1]"),
+            Err(crate::Error::Decode(_))
+        ));
+        assert!(Response::from_file("test/missing.json").is_err());
+    }
+
+    #[test]
+    fn full_text_hierarchy() {
+        let symbol = |text: &str, detected_break: Option<&str>| {
+            let mut symbol = json!({ "text": text });
+            if let Some(detected_break) = detected_break {
+                symbol["property"] = json!({ "detectedBreak": { "type": detected_break } });
+            }
+            symbol
+        };
+        let mut full_text = full_text_json(vec![vec![vec![
+            json!({ "symbols": [symbol("H", None), symbol("i", Some("SPACE"))] }),
+            json!({ "symbols": [
+                symbol("y", None),
+                symbol("o", None),
+                symbol("u", Some("LINE_BREAK"))
+            ] }),
+        ]]]);
+        full_text["text"] = json!("Hi you\n");
+        let page = &mut full_text["pages"][0];
+        page["property"] =
+            json!({ "detectedLanguages": [{ "languageCode": "en", "confidence": 0.9 }] });
+        page["width"] = json!(640);
+        page["height"] = json!(480);
+        page["blocks"][0]["confidence"] = json!(0.98);
+        let response = Response::new(json!({ "fullTextAnnotation": full_text }), "").unwrap();
+
+        let full_text = response.full_text_annotations().unwrap();
+        let page = &full_text.pages[0];
+        assert_eq!((page.width, page.height), (640, 480));
+        assert_eq!(page.property.as_ref().unwrap().language(), Some("en"));
+        assert_eq!(page.blocks[0].confidence, 0.98);
+        let paragraph = &page.blocks[0].paragraphs[0];
+        assert_eq!(paragraph.words[0].text(), "Hi");
+        assert_eq!(paragraph.words[1].text(), "you");
+        assert_eq!(paragraph.text(), "Hi you\n");
+        assert_eq!(page.text(), full_text.text);
+        let words: Vec<_> = full_text.words().map(Word::text).collect();
+        assert_eq!(words, ["Hi", "you"]);
+        assert_eq!(full_text.symbols().count(), 5);
+        assert_eq!(full_text.blocks().count(), 1);
+        assert_eq!(response.full_text(), "Hi you\n");
+        let mut without_text = response.raw().clone();
+        without_text["fullTextAnnotation"]
+            .as_object_mut()
+            .unwrap()
+            .remove("text");
+        assert_eq!(
+            Response::new(without_text, "").unwrap().full_text(),
+            "Hi you\n"
+        );
+        let text_detection =
+            json!({ "textAnnotations": [{ "description": "Hi you\n", "boundingPoly": {} }] });
+        assert_eq!(
+            Response::new(text_detection, "").unwrap().full_text(),
+            "Hi you\n"
+        );
+
+        // re-serializes in the format of the API
+        let json = serde_json::to_value(response.annotations()).unwrap();
+        let block = &json["fullTextAnnotation"]["pages"][0]["blocks"][0];
+        assert_eq!(block["blockType"], "TEXT");
+        assert_eq!(
+            block["boundingBox"]["vertices"][0],
+            json!({ "x": 0, "y": 0 })
+        );
+        let reparsed: crate::AnnotateImageResponse = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(reparsed).unwrap(), json);
+    }
+
+    #[test]
+    fn prefix_breaks() {
+        let mut a = word("a", Rect::default());
+        a["property"] = json!({ "detectedLanguages": [
+            { "languageCode": "en", "confidence": 0.4 },
+            { "languageCode": "fr", "confidence": 0.6 }
+        ] });
+        let mut b = word("b", Rect::default());
+        b["symbols"][0]["property"] =
+            json!({ "detectedBreak": { "type": "SPACE", "isPrefix": true } });
+        let full_text = full_text(vec![a, b]);
+        let paragraph = full_text.paragraphs().next().unwrap();
+
+        assert_eq!(paragraph.text(), "a b");
+        assert_eq!(paragraph.words[0].language(), Some("fr"));
+        assert_eq!(paragraph.words[1].language(), None);
+    }
+
+    #[test]
+    fn masked_full_text_annotation() {
+        let response =
+            Response::from_json_str(r#"{"fullTextAnnotation": {"text": "Hello world\n"}}"#)
+                .unwrap();
+        let full_text = response.full_text_annotations().unwrap();
+        assert_eq!(full_text.text, "Hello world\n");
+        assert!(full_text.pages.is_empty());
+
+        let response = Response::from_json_str(
+            r#"{"fullTextAnnotation": {"pages": [{"blocks": [{"paragraphs": [{"words": [
+                {"symbols": [{"text": "H"}, {"text": "i"}]}
+            ]}]}]}]}}"#,
+        )
+        .unwrap();
+        let full_text = response.full_text_annotations().unwrap();
+        assert_eq!(full_text.pages[0].text(), "Hi");
+        assert_eq!(response.full_text(), "Hi");
+        let block = &full_text.pages[0].blocks[0];
+        assert_eq!(block.block_type, "");
+        assert!(block.bounding_box.vertices.is_empty());
+        assert_eq!(block.bounding_box.rect(), Rect::new(0, 0, 0, 0));
+    }
+
+    #[test]
+    fn batch_annotate_images_response() {
+        let batch: crate::BatchAnnotateImagesResponse = serde_json::from_value(json!({
+            "responses": [
+                { "labelAnnotations": [{ "description": "Cat", "score": 0.9 }] },
+                { "error": { "code": 3, "message": "Bad image data." } }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(
+            batch.get(0).unwrap().label_annotations[0].description,
+            "Cat"
+        );
+        assert_eq!(batch.get(1).unwrap().error.as_ref().unwrap().code, 3);
+        assert!(batch.get(2).is_none());
+        assert_eq!(batch.iter().count(), 2);
+    }
+
+    #[test]
+    fn map_geometry() {
+        let mut response = Response::new(
+            json!({
+                "textAnnotations": [
+                    {
+                        "description": "HP",
+                        "boundingPoly": { "vertices": [{ "x": 5 }, { "x": 20, "y": 10 }] }
+                    }
+                ],
+                "faceAnnotations": [
+                    {
+                        "boundingPoly": {},
+                        "fdBoundingPoly": {},
+                        "landmarks": [{ "type": "NOSE_TIP", "position": { "x": 1.5, "y": 2, "z": 3 } }]
+                    }
+                ]
+            }),
+            "",
+        )
+        .unwrap();
+
+        response
+            .map_geometry(&Transform::translate(100.0, 200.0))
+            .unwrap();
+
+        let text_annotations = response.text_annotations();
+        assert_eq!(
+            text_annotations[0].bounding_poly.vertices,
+            [Point { x: 105, y: 200 }, Point { x: 120, y: 210 }]
+        );
+        assert_eq!(
+            response.raw()["faceAnnotations"][0]["landmarks"][0]["position"],
+            json!({ "x": 101.5, "y": 202.0, "z": 3 })
+        );
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn reuses_connections() {
+        let responses = vec![(200, r#"{"responses": [{}]}"#.to_string()); 3];
+        let server = TestServer::start(responses).await;
+        let client = server.client();
+        let request = image_request();
+
+        client.annotate(&request).await.unwrap();
+        client.annotate(&request).await.unwrap();
+        client.clone().annotate(&request).await.unwrap();
+
+        assert_eq!(server.requests().await.len(), 3);
+        assert_eq!(server.connections(), 1);
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn fields() {
+        // The mask leaves out the bounding polygons.
+        let body = r#"{"responses": [{"textAnnotations": [{"description": "Hi"}]}]}"#;
+        let server = TestServer::start(vec![(200, body.to_string())]).await;
+        let client = Client::builder()
+            .credential(crate::Credential::access_token("TOKEN"))
+            .endpoint(&server.url())
+            .build()
+            .unwrap();
+        let request =
+            AnnotateRequestBuilder::new(ImageSource::ImageUri("https://example.com/a.png".into()))
+                .feature(Feature::TextDetection)
+                .fields("responses.textAnnotations.description")
+                .build();
+
+        let response = client.annotate(&request).await.unwrap();
+        let texts = response.text_annotations();
+        assert_eq!(texts[0].description, "Hi");
+        assert!(texts[0].bounding_poly.vertices.is_empty());
+
+        let requests = server.requests().await;
+        assert!(requests[0].starts_with(
+            "POST /v1/images:annotate?fields=responses.textAnnotations.description HTTP/1.1\r\n"
+        ));
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn api_error() {
+        let body =
+            r#"{"error": {"code": 403, "message": "denied", "status": "PERMISSION_DENIED"}}"#;
+        let server = TestServer::start(vec![
+            (403, body.to_string()),
+            (502, "<html>Bad Gateway</html>".to_string()),
+            (500, r#"{"error": "internal"}"#.to_string()),
+            (200, "<html>OK</html>".to_string()),
+        ])
+        .await;
+        let client = server.client();
+        let request = image_request();
+
+        let error = client.annotate(&request).await.err().unwrap();
+        let crate::Error::Api { error, response } = error else {
+            panic!("expected an API error, got {:?}", error);
+        };
+        assert_eq!(response.status, 403);
+        assert_eq!(error.code, 403);
+        assert_eq!(error.status.as_deref(), Some("PERMISSION_DENIED"));
+        assert_eq!(error.message, "denied");
+
+        let error = client.annotate(&request).await.err().unwrap();
+        let crate::Error::Status(response) = error else {
+            panic!("expected an HTTP status error, got {:?}", error);
+        };
+        assert_eq!(response.status, 502);
+        assert_eq!(response.body, "<html>Bad Gateway</html>");
+        assert_eq!(
+            response.headers,
+            [("content-type".to_string(), "application/json".to_string())]
+        );
+
+        // not the error object of the API
+        let error = client.annotate(&request).await.err().unwrap();
+        assert!(matches!(&error, crate::Error::Status(response) if response.status == 500));
+
+        let error = client.annotate(&request).await.err().unwrap();
+        assert!(matches!(error, crate::Error::Decode(_)));
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn quota_exceeded() {
+        let body = r#"{"error": {"code": 429, "message": "Quota exceeded", "status": "RESOURCE_EXHAUSTED"}}"#;
+        let server = TestServer::start(vec![(429, body.to_string())]).await;
+        let client = server.client();
+        let request = image_request();
+
+        let error = client.annotate(&request).await.err().unwrap();
+        assert!(error.is_retryable());
+        let crate::Error::QuotaExceeded {
+            error, response, ..
+        } = error
+        else {
+            panic!("expected an exceeded quota, got {:?}", error);
+        };
+        assert_eq!(response.status, 429);
+        assert_eq!(error.unwrap().message, "Quota exceeded");
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn deadline() {
+        let server = TestServer::start_silent().await;
+        let client = server.client();
+        let request =
+            AnnotateRequestBuilder::new(ImageSource::ImageUri("https://example.com/a.png".into()))
+                .deadline(std::time::Duration::from_millis(100))
+                .build();
+
+        let error = client.annotate(&request).await.err().unwrap();
+        assert!(matches!(error, crate::Error::Timeout));
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn cancellation() {
+        let server = TestServer::start_silent().await;
+        let client = server.client();
+        let token = CancellationToken::new();
+        let client = client.with_cancellation(token.clone());
+        let request = image_request();
+
+        let call = tokio::spawn(async move { client.annotate(&request).await.err() });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        token.cancel();
+
+        let error = call.await.unwrap().unwrap();
+        assert!(matches!(error, crate::Error::Cancelled));
+    }
+
+    #[test]
+    fn bounding_box_geometry() {
+        // Text rotated by 180°: the first vertex is the bottom-right corner.
+        let bounding_box: BoundingBox = serde_json::from_value(json!({ "vertices": [
+            { "x": 110, "y": 60 }, { "x": 10, "y": 60 },
+            { "x": 10, "y": 20 }, { "x": 110, "y": 20 }
+        ] }))
+        .unwrap();
+
+        assert_eq!(bounding_box.left_top(), Point { x: 10, y: 20 });
+        assert_eq!(bounding_box.right_bottom(), Point { x: 110, y: 60 });
+        assert_eq!(bounding_box.center(), Point { x: 60, y: 40 });
+        assert_eq!((bounding_box.width(), bounding_box.height()), (100, 40));
+    }
+
+    #[test]
+    fn omitted_zero_coordinates() {
+        // A word in the top-left corner of the image.
+        let response = Response::from_json_str(
+            &json!({
+                "textAnnotations": [{
+                    "description": "Corner",
+                    "boundingPoly": { "vertices": [
+                        {}, { "x": 60 }, { "x": 60, "y": 12 }, { "y": 12 }
+                    ] }
+                }],
+                "localizedObjectAnnotations": [{
+                    "name": "Sign",
+                    "score": 0.9,
+                    "boundingPoly": { "normalizedVertices": [
+                        {}, { "x": 0.5 }, { "x": 0.5, "y": 0.25 }, { "y": 0.25 }
+                    ] }
+                }]
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let annotations = response.annotations();
+        let vertices = &annotations.text_annotations[0].bounding_poly.vertices;
+        assert_eq!(vertices[0], Point { x: 0, y: 0 });
+        assert_eq!(vertices[3], Point { x: 0, y: 12 });
+
+        let object = &annotations.localized_object_annotations[0].bounding_poly;
+        assert_eq!(
+            object.normalized_vertices[1].to_point(200, 100),
+            Point { x: 100, y: 0 }
+        );
+    }
 }
@@ -0,0 +1,153 @@
+use crate::{FullTextAnnotation, Rect, Word};
+
+/// A visual line of text: words side by side, which the API may have put in different
+/// paragraphs or blocks, e.g. the item and the price on a receipt.
+#[derive(Debug, Clone)]
+pub struct Line<'a> {
+    /// The words from left to right, separated by spaces.
+    pub text: String,
+    /// The box enclosing all words.
+    pub bounding_box: Rect,
+    pub words: Vec<&'a Word>,
+}
+
+/// A line while words are being added, with the bounds of each of its words.
+struct Cluster<T> {
+    words: Vec<(T, Rect)>,
+}
+
+impl<T> Cluster<T> {
+    /// The median bottom edge of the words, robust against descenders and punctuation.
+    fn baseline(&self) -> i64 {
+        let mut bottoms: Vec<_> = self.words.iter().map(|(_, rect)| rect.bottom).collect();
+        bottoms.sort_unstable();
+        bottoms[bottoms.len() / 2]
+    }
+
+    fn median_height(&self) -> i64 {
+        let mut heights: Vec<_> = self.words.iter().map(|(_, rect)| rect.height()).collect();
+        heights.sort_unstable();
+        heights[heights.len() / 2]
+    }
+
+    fn top(&self) -> i64 {
+        self.words
+            .iter()
+            .map(|(_, rect)| rect.top)
+            .min()
+            .unwrap_or(0)
+    }
+
+    fn bottom(&self) -> i64 {
+        self.words
+            .iter()
+            .map(|(_, rect)| rect.bottom)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Whether `rect` lies on this line: it overlaps the line vertically by at least half
+    /// of the smaller height and its bottom is near the baseline.
+    fn accepts(&self, rect: &Rect) -> bool {
+        let height = self.median_height().min(rect.height()).max(1);
+        let overlap = self.bottom().min(rect.bottom) - self.top().max(rect.top);
+
+        overlap * 2 >= height && (rect.bottom - self.baseline()).abs() * 2 <= height
+    }
+}
+
+/// Groups `words` into visual lines, see [`FullTextAnnotation::lines`]: the lines from
+/// top to bottom, each with its words from left to right and the box enclosing them.
+pub(crate) fn cluster_lines<T>(mut words: Vec<(T, Rect)>) -> Vec<(Vec<T>, Rect)> {
+    words.sort_by_key(|(_, rect)| (rect.top + rect.bottom, rect.left));
+
+    let mut clusters: Vec<Cluster<T>> = vec![];
+    for (word, rect) in words {
+        match clusters.iter_mut().rev().find(|line| line.accepts(&rect)) {
+            Some(line) => line.words.push((word, rect)),
+            None => clusters.push(Cluster {
+                words: vec![(word, rect)],
+            }),
+        }
+    }
+
+    let mut lines: Vec<_> = clusters
+        .into_iter()
+        .map(|mut cluster| {
+            cluster.words.sort_by_key(|(_, rect)| rect.left);
+            let bounding_box = cluster
+                .words
+                .iter()
+                .map(|(_, rect)| *rect)
+                .reduce(|a, b| a.union(&b))
+                .unwrap_or_default();
+            let words = cluster.words.into_iter().map(|(word, _)| word).collect();
+            (words, bounding_box)
+        })
+        .collect();
+    lines.sort_by_key(|(_, rect)| (rect.top, rect.left));
+    lines
+}
+
+impl FullTextAnnotation {
+    /// Groups the words into visual lines, from top to bottom, by how they overlap
+    /// vertically and line up on a common baseline. Assumes roughly horizontal text.
+    /// ```no_run
+    /// # fn f(full_text: gcv_client::FullTextAnnotation) {
+    /// for line in full_text.lines() {
+    ///     println!("{:>5} {}", line.bounding_box.top, line.text);
+    /// }
+    /// # }
+    /// ```
+    pub fn lines(&self) -> Vec<Line<'_>> {
+        let words: Vec<_> = self
+            .words()
+            .map(|word| (word, word.bounding_box.rect()))
+            .filter(|(_, rect)| !rect.is_empty())
+            .collect();
+
+        cluster_lines(words)
+            .into_iter()
+            .map(|(words, bounding_box): (Vec<&Word>, _)| {
+                let text = words
+                    .iter()
+                    .map(|word| word.text())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                Line {
+                    text,
+                    bounding_box,
+                    words,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_fixtures::{full_text_of_blocks, word};
+    use crate::Rect;
+
+    #[test]
+    fn lines() {
+        // A receipt where the API put the items and the prices in separate blocks.
+        let full_text = full_text_of_blocks(vec![
+            vec![vec![
+                word("Coffee", Rect::new(10, 10, 70, 30)),
+                word("Bagel", Rect::new(10, 42, 60, 60)),
+            ]],
+            vec![vec![
+                word("3.50", Rect::new(200, 12, 240, 31)),
+                word("2.25", Rect::new(200, 41, 240, 61)),
+            ]],
+        ]);
+
+        let lines = full_text.lines();
+
+        let texts: Vec<_> = lines.iter().map(|line| line.text.as_str()).collect();
+        assert_eq!(texts, ["Coffee 3.50", "Bagel 2.25"]);
+        assert_eq!(lines[0].bounding_box, Rect::new(10, 10, 240, 31));
+        assert_eq!(lines[1].words.len(), 2);
+    }
+}
@@ -0,0 +1,194 @@
+use crate::lines::cluster_lines;
+use crate::{FullTextAnnotation, Page, Rect};
+
+/// The start and end of a gap along one axis.
+type Gap = (i64, i64);
+
+/// Sorts `items` into reading order: columns from left to right, each from top to bottom.
+///
+/// Items are split recursively at gaps that no item bridges, first into columns and then
+/// into rows, so a heading across two columns comes before both of them. Rows that share
+/// a gap between columns are read column by column. Items that overlap both ways are
+/// ordered by their top edge.
+pub fn sort_reading_order<T>(items: &mut Vec<T>, rect: impl Fn(&T) -> Rect) {
+    let mut keyed: Vec<_> = items.drain(..).map(|item| (rect(&item), item)).collect();
+    sort_keyed(&mut keyed);
+    items.extend(keyed.into_iter().map(|(_, item)| item));
+}
+
+fn sort_keyed<T>(items: &mut Vec<(Rect, T)>) {
+    if items.len() < 2 {
+        return;
+    }
+
+    let columns = split(items, |rect| (rect.left, rect.right));
+    if columns.len() > 1 {
+        for mut column in columns {
+            sort_keyed(&mut column);
+            items.extend(column);
+        }
+        return;
+    }
+
+    let mut all: Vec<_> = columns.into_iter().flatten().collect();
+    let rows = split(&mut all, |rect| (rect.top, rect.bottom));
+    if rows.len() == 1 {
+        *items = rows.into_iter().flatten().collect();
+        items.sort_by_key(|(rect, _)| (rect.top, rect.left));
+        return;
+    }
+
+    // Lines of side-by-side columns are often level with each other, so keep rows
+    // together while they share a gutter, to read them column by column.
+    let mut sections: Vec<(Vec<_>, Vec<Gap>)> = vec![];
+    for row in rows {
+        let gutters = gutters(&row);
+        if let Some((section, shared)) = sections.last_mut() {
+            let common = intersect(shared, &gutters);
+            if !common.is_empty() {
+                section.extend(row);
+                *shared = common;
+                continue;
+            }
+        }
+        sections.push((row, gutters));
+    }
+    for (mut section, _) in sections {
+        sort_keyed(&mut section);
+        items.extend(section);
+    }
+}
+
+/// The horizontal gaps that no item in `row` bridges.
+fn gutters<T>(row: &[(Rect, T)]) -> Vec<Gap> {
+    let mut extents: Vec<_> = row
+        .iter()
+        .map(|(rect, _)| (rect.left, rect.right))
+        .collect();
+    extents.sort_unstable();
+
+    let mut gutters = vec![];
+    let mut end = i64::MIN;
+    for (start, stop) in extents {
+        if end != i64::MIN && start >= end {
+            gutters.push((end, start));
+        }
+        end = end.max(stop);
+    }
+    gutters
+}
+
+/// The overlapping parts of two sets of gaps.
+fn intersect(a: &[Gap], b: &[Gap]) -> Vec<Gap> {
+    a.iter()
+        .flat_map(|&(a_start, a_end)| {
+            b.iter().filter_map(move |&(b_start, b_end)| {
+                let (start, end) = (a_start.max(b_start), a_end.min(b_end));
+                (start < end).then_some((start, end))
+            })
+        })
+        .collect()
+}
+
+/// Splits `items` into groups at the gaps between their extents along one axis, in
+/// ascending order.
+fn split<T>(
+    items: &mut Vec<(Rect, T)>,
+    extent: impl Fn(&Rect) -> (i64, i64),
+) -> Vec<Vec<(Rect, T)>> {
+    items.sort_by_key(|(rect, _)| extent(rect).0);
+
+    let mut groups: Vec<Vec<(Rect, T)>> = vec![];
+    let mut end = i64::MIN;
+    for (rect, item) in items.drain(..) {
+        let (start, stop) = extent(&rect);
+        match groups.last_mut() {
+            Some(group) if start < end => group.push((rect, item)),
+            _ => groups.push(vec![(rect, item)]),
+        }
+        end = end.max(stop);
+    }
+    groups
+}
+
+impl FullTextAnnotation {
+    /// Sorts the blocks of each page and the paragraphs within them into reading order,
+    /// see [`sort_reading_order`]. The API doesn't guarantee an order, e.g. for documents
+    /// with two columns.
+    ///
+    /// The words of a paragraph are read line by line, see [`lines`](Self::lines), as
+    /// gaps between them, e.g. after a label, don't separate columns. [`text`](Self::text)
+    /// is put together again in the new order, with the breaks detected after each symbol.
+    pub fn sort_reading_order(&mut self) {
+        for page in &mut self.pages {
+            sort_reading_order(&mut page.blocks, |block| block.bounding_box.rect());
+            for block in &mut page.blocks {
+                sort_reading_order(&mut block.paragraphs, |paragraph| {
+                    paragraph.bounding_box.rect()
+                });
+                for paragraph in &mut block.paragraphs {
+                    let words = paragraph
+                        .words
+                        .drain(..)
+                        .map(|word| {
+                            let rect = word.bounding_box.rect();
+                            (word, rect)
+                        })
+                        .collect();
+                    paragraph.words = cluster_lines(words)
+                        .into_iter()
+                        .flat_map(|(words, _)| words)
+                        .collect();
+                }
+            }
+        }
+        self.text = self.pages.iter().map(Page::text).collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sort_reading_order;
+    use crate::test_fixtures::{full_text, word};
+    use crate::Rect;
+    use serde_json::json;
+
+    #[test]
+    fn two_columns() {
+        // A heading across two columns, whose lines are level with each other.
+        let mut items = vec![
+            ("left 2", Rect::new(0, 40, 90, 50)),
+            ("right 1", Rect::new(110, 20, 200, 30)),
+            ("heading", Rect::new(0, 0, 200, 10)),
+            ("right 2", Rect::new(110, 40, 200, 50)),
+            ("left 1", Rect::new(0, 20, 90, 30)),
+        ];
+
+        sort_reading_order(&mut items, |(_, rect)| *rect);
+
+        let names: Vec<_> = items.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, ["heading", "left 1", "left 2", "right 1", "right 2"]);
+    }
+
+    #[test]
+    fn words_by_line() {
+        // A form whose labels and values are far apart, given out of order.
+        let word = |text: &str, left: i64, top: i64| {
+            let mut word = word(text, Rect::new(left, top, left + 40, top + 10));
+            word["symbols"][0]["property"] = json!({ "detectedBreak": { "type": "SPACE" } });
+            word
+        };
+        let mut full_text = full_text(vec![
+            word("42", 200, 21),
+            word("Name:", 0, 0),
+            word("Age:", 0, 20),
+            word("Bob", 200, 1),
+        ]);
+
+        full_text.sort_reading_order();
+
+        let texts: Vec<_> = full_text.words().map(|word| word.text()).collect();
+        assert_eq!(texts, ["Name:", "Bob", "Age:", "42"]);
+        assert_eq!(full_text.text, "Name: Bob Age: 42 ");
+    }
+}
@@ -0,0 +1,111 @@
+//! Masks credentials in what the crate surfaces, such as errors and `Debug` output, so
+//! they don't end up in logs or error trackers.
+
+use url::Url;
+
+const REDACTED: &str = "REDACTED";
+
+/// Query parameters that carry a credential.
+const SENSITIVE_PARAMS: [&str; 2] = ["key", "access_token"];
+
+/// Headers that carry a credential.
+const SENSITIVE_HEADERS: [&str; 4] = [
+    "authorization",
+    "proxy-authorization",
+    "x-goog-api-key",
+    "cookie",
+];
+
+/// Replaces the values of credential parameters in `url`.
+pub(crate) fn redact_url(url: &mut Url) {
+    if !url
+        .query_pairs()
+        .any(|(name, _)| SENSITIVE_PARAMS.contains(&name.as_ref()))
+    {
+        return;
+    }
+
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(name, value)| {
+            let value = match SENSITIVE_PARAMS.contains(&name.as_ref()) {
+                true => REDACTED.to_string(),
+                false => value.into_owned(),
+            };
+            (name.into_owned(), value)
+        })
+        .collect();
+    url.query_pairs_mut().clear().extend_pairs(pairs);
+}
+
+/// `url` with the credential parameters replaced, or as is if it isn't a valid URL.
+pub(crate) fn redacted_url(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(mut url) => {
+            redact_url(&mut url);
+            url.into()
+        }
+        Err(_) => url.to_string(),
+    }
+}
+
+/// `value`, or a placeholder if the header `name` carries a credential.
+pub(crate) fn redacted_header<'a>(name: &str, value: &'a str) -> &'a str {
+    match SENSITIVE_HEADERS
+        .iter()
+        .any(|sensitive| name.eq_ignore_ascii_case(sensitive))
+    {
+        true => REDACTED,
+        false => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{redacted_header, redacted_url};
+    use crate::HttpRequest;
+
+    #[test]
+    fn redact() {
+        assert_eq!(
+            redacted_url("https://vision.googleapis.com/v1/images:annotate?fields=a&key=SECRET"),
+            "https://vision.googleapis.com/v1/images:annotate?fields=a&key=REDACTED"
+        );
+        assert_eq!(redacted_url("http://localhost/"), "http://localhost/");
+        assert_eq!(
+            redacted_header("Authorization", "Bearer SECRET"),
+            "REDACTED"
+        );
+        assert_eq!(redacted_header("x-goog-user-project", "p"), "p");
+
+        let request = HttpRequest::post_json(
+            "https://vision.googleapis.com/v1/images:annotate?key=SECRET",
+            b"{}".to_vec(),
+        )
+        .header("Authorization", "Bearer SECRET");
+        assert!(!format!("{:?}", request).contains("SECRET"));
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn errors_hide_the_api_key() {
+        use crate::test_server::image_request;
+        use crate::{Client, Credential};
+
+        // Nothing listens on the port once the listener is dropped.
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let url = format!("http://{}", addr);
+        let client = Client::builder()
+            .credential(Credential::ApiKey("SECRET".to_string()))
+            .endpoint(&url)
+            .build()
+            .unwrap();
+        let request = image_request();
+
+        let error = client.annotate(&request).await.err().unwrap();
+        assert!(!format!("{} {:?}", error, error).contains("SECRET"));
+    }
+}
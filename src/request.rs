@@ -0,0 +1,565 @@
+use crate::{ImageGCV, LatLng};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Detection types that can be requested for an image.
+#[derive(Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Feature {
+    /// Sparse OCR, better suited to text in photos such as signs.
+    TextDetection,
+    /// Dense OCR optimized for documents.
+    DocumentTextDetection,
+    LabelDetection,
+    FaceDetection,
+    ObjectLocalization,
+    SafeSearchDetection,
+    ImageProperties,
+    CropHints,
+    WebDetection,
+    LogoDetection,
+    LandmarkDetection,
+}
+
+impl Feature {
+    /// Requests this feature with at most `max_results` annotations.
+    pub fn max_results(self, max_results: u32) -> FeatureRequest {
+        FeatureRequest::from(self).max_results(max_results)
+    }
+
+    pub fn model(self, model: impl Into<String>) -> FeatureRequest {
+        FeatureRequest::from(self).model(model)
+    }
+}
+
+/// A feature along with its per-feature options.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FeatureRequest {
+    #[serde(rename = "type")]
+    pub feature: Feature,
+    /// Maximum number of results of this type. Ignored by `TEXT_DETECTION`,
+    /// `DOCUMENT_TEXT_DETECTION` and `CROP_HINTS`.
+    #[serde(rename = "maxResults", skip_serializing_if = "Option::is_none")]
+    pub max_results: Option<u32>,
+    /// Model to use, `builtin/stable` (the default) or `builtin/latest`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+impl FeatureRequest {
+    pub fn max_results(mut self, max_results: u32) -> Self {
+        self.max_results = Some(max_results);
+        self
+    }
+
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+}
+
+impl From<Feature> for FeatureRequest {
+    fn from(feature: Feature) -> Self {
+        Self {
+            feature,
+            max_results: None,
+            model: None,
+        }
+    }
+}
+
+/// A single image together with the detections to run on it.
+///
+/// This serializes to exactly one entry of the `requests` array sent to `images:annotate`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct AnnotateImageRequest {
+    pub image: ImageSource,
+    pub features: Vec<FeatureRequest>,
+    #[serde(rename = "imageContext", skip_serializing_if = "Option::is_none")]
+    pub image_context: Option<ImageContext>,
+    /// Field mask of the response, e.g. `responses.textAnnotations.description`. Not part
+    /// of the body but the `fields` query parameter of the call, so within a batch it
+    /// applies only when every request has one, combining them.
+    #[serde(skip)]
+    pub fields: Option<String>,
+    /// Time limit for the call including retries, overriding the timeout of the client
+    /// when shorter. A batch uses the shortest deadline of its requests.
+    #[serde(skip)]
+    pub deadline: Option<Duration>,
+    /// Identifies the image in errors of [`Client::annotate_many`](crate::Client::annotate_many),
+    /// e.g. its path. Not sent to the API.
+    #[serde(skip)]
+    pub tag: Option<String>,
+}
+
+/// Where the API reads the image of an [`AnnotateImageRequest`] from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageSource {
+    /// Base64 encoded image bytes sent inline.
+    Content(String),
+    /// An object in Google Cloud Storage, e.g. `gs://bucket/image.png`.
+    /// The caller's credentials need read access to it.
+    GcsUri(String),
+    /// A publicly accessible HTTP(S) URL. Google may throttle or refuse
+    /// fetching from some hosts, so prefer [`ImageSource::GcsUri`] in production.
+    ImageUri(String),
+}
+
+impl Serialize for ImageSource {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(1))?;
+        match self {
+            ImageSource::Content(content) => map.serialize_entry("content", content)?,
+            ImageSource::GcsUri(uri) => {
+                map.serialize_entry("source", &HashMap::from([("gcsImageUri", uri)]))?
+            }
+            ImageSource::ImageUri(uri) => {
+                map.serialize_entry("source", &HashMap::from([("imageUri", uri)]))?
+            }
+        }
+        map.end()
+    }
+}
+
+impl From<ImageGCV> for ImageSource {
+    fn from(image: ImageGCV) -> Self {
+        ImageSource::Content(image.base64_data)
+    }
+}
+
+impl From<&ImageGCV> for ImageSource {
+    fn from(image: &ImageGCV) -> Self {
+        ImageSource::Content(image.base64_data.clone())
+    }
+}
+
+/// Body of an `images:annotate` call.
+#[derive(Serialize)]
+pub(crate) struct BatchAnnotateImagesRequest<'a> {
+    pub requests: &'a [AnnotateImageRequest],
+}
+
+impl BatchAnnotateImagesRequest<'_> {
+    /// The `fields` query parameter: the distinct masks of the requests, or `None` if
+    /// any of them wants the full response.
+    pub fn fields(&self) -> Option<String> {
+        let mut masks: Vec<&str> = vec![];
+        for request in self.requests {
+            let mask = request.fields.as_deref()?;
+            if !masks.contains(&mask) {
+                masks.push(mask);
+            }
+        }
+
+        Some(masks.join(","))
+    }
+
+    pub fn deadline(&self) -> Option<Duration> {
+        self.requests
+            .iter()
+            .filter_map(|request| request.deadline)
+            .min()
+    }
+}
+
+/// Additional hints and parameters for the detections.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ImageContext {
+    /// BCP-47 language codes of the text in the image, e.g. `["ja", "en"]`.
+    /// Leaving this empty lets the API detect the language automatically.
+    #[serde(rename = "languageHints", skip_serializing_if = "Vec::is_empty")]
+    pub language_hints: Vec<String>,
+    #[serde(rename = "cropHintsParams", skip_serializing_if = "Option::is_none")]
+    pub crop_hints_params: Option<CropHintsParams>,
+    #[serde(rename = "webDetectionParams", skip_serializing_if = "Option::is_none")]
+    pub web_detection_params: Option<WebDetectionParams>,
+    #[serde(
+        rename = "textDetectionParams",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub text_detection_params: Option<TextDetectionParams>,
+    /// Area the image was taken in, used by landmark and web detection.
+    #[serde(rename = "latLongRect", skip_serializing_if = "Option::is_none")]
+    pub lat_long_rect: Option<LatLongRect>,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct CropHintsParams {
+    /// Aspect ratios (width / height) to return crop hints for, at most 16.
+    /// The API uses `1.0` when empty.
+    #[serde(rename = "aspectRatios", skip_serializing_if = "Vec::is_empty")]
+    pub aspect_ratios: Vec<f32>,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct WebDetectionParams {
+    /// Include results derived from the geo information in the image.
+    #[serde(rename = "includeGeoResults")]
+    pub include_geo_results: bool,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextDetectionParams {
+    /// Report a confidence score for each `textAnnotations` entry.
+    #[serde(rename = "enableTextDetectionConfidenceScore")]
+    pub enable_text_detection_confidence_score: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Default, PartialEq)]
+pub struct LatLongRect {
+    #[serde(rename = "minLatLng")]
+    pub min_lat_lng: LatLng,
+    #[serde(rename = "maxLatLng")]
+    pub max_lat_lng: LatLng,
+}
+
+/// Language hint that switches OCR to the handwriting recognition model.
+const HANDWRITING_LANGUAGE_HINT: &str = "en-t-i0-handwrit";
+
+/// Builds an [`AnnotateImageRequest`] that combines several features in one billed call.
+///
+/// ```no_run
+/// # fn f(image: gcv_client::ImageGCV) {
+/// use gcv_client::{AnnotateRequestBuilder, Feature};
+///
+/// let request = AnnotateRequestBuilder::new(image)
+///     .feature(Feature::DocumentTextDetection)
+///     .feature(Feature::LabelDetection.max_results(5))
+///     .build();
+/// # }
+/// ```
+pub struct AnnotateRequestBuilder {
+    image: ImageSource,
+    features: Vec<FeatureRequest>,
+    image_context: Option<ImageContext>,
+    fields: Option<String>,
+    deadline: Option<Duration>,
+    tag: Option<String>,
+}
+
+impl AnnotateRequestBuilder {
+    pub fn new(image: impl Into<ImageSource>) -> Self {
+        Self {
+            image: image.into(),
+            features: vec![],
+            image_context: None,
+            fields: None,
+            deadline: None,
+            tag: None,
+        }
+    }
+
+    pub fn feature(mut self, feature: impl Into<FeatureRequest>) -> Self {
+        self.features.push(feature.into());
+        self
+    }
+
+    pub fn features<F: Into<FeatureRequest>>(
+        mut self,
+        features: impl IntoIterator<Item = F>,
+    ) -> Self {
+        self.features.extend(features.into_iter().map(Into::into));
+        self
+    }
+
+    /// Preset for handwritten notes: `DOCUMENT_TEXT_DETECTION` on the latest model
+    /// with the handwriting language hint.
+    pub fn handwriting(self) -> Self {
+        self.feature(Feature::DocumentTextDetection.model("builtin/latest"))
+            .language_hints([HANDWRITING_LANGUAGE_HINT])
+    }
+
+    pub fn image_context(mut self, image_context: ImageContext) -> Self {
+        self.image_context = Some(image_context);
+        self
+    }
+
+    pub fn language_hints<S: Into<String>>(mut self, hints: impl IntoIterator<Item = S>) -> Self {
+        self.image_context_mut()
+            .language_hints
+            .extend(hints.into_iter().map(Into::into));
+        self
+    }
+
+    /// Asks `CROP_HINTS` for one hint per aspect ratio (width / height), e.g. `[16.0 / 9.0, 1.0]`.
+    pub fn crop_hints_aspect_ratios(
+        mut self,
+        aspect_ratios: impl IntoIterator<Item = f32>,
+    ) -> Self {
+        self.image_context_mut()
+            .crop_hints_params
+            .get_or_insert_with(Default::default)
+            .aspect_ratios
+            .extend(aspect_ratios);
+        self
+    }
+
+    pub fn include_geo_results(mut self, include_geo_results: bool) -> Self {
+        self.image_context_mut()
+            .web_detection_params
+            .get_or_insert_with(Default::default)
+            .include_geo_results = include_geo_results;
+        self
+    }
+
+    pub fn enable_text_detection_confidence_score(mut self, enable: bool) -> Self {
+        self.image_context_mut()
+            .text_detection_params
+            .get_or_insert_with(Default::default)
+            .enable_text_detection_confidence_score = enable;
+        self
+    }
+
+    pub fn lat_long_rect(mut self, min_lat_lng: LatLng, max_lat_lng: LatLng) -> Self {
+        self.image_context_mut().lat_long_rect = Some(LatLongRect {
+            min_lat_lng,
+            max_lat_lng,
+        });
+        self
+    }
+
+    /// Returns only the parts of the response selected by the field mask `fields`, e.g.
+    /// `responses.textAnnotations.description` to skip the symbol geometry of OCR.
+    pub fn fields(mut self, fields: impl Into<String>) -> Self {
+        self.fields = Some(fields.into());
+        self
+    }
+
+    /// Fails the call if it, including any retries, takes longer than `deadline`, e.g. a
+    /// tight limit for interactive requests on a client shared with batch jobs.
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Identifies the image in errors of bulk operations, e.g. its path or a database key.
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    pub fn build(self) -> AnnotateImageRequest {
+        AnnotateImageRequest {
+            image: self.image,
+            features: self.features,
+            image_context: self.image_context,
+            fields: self.fields,
+            deadline: self.deadline,
+            tag: self.tag,
+        }
+    }
+
+    fn image_context_mut(&mut self) -> &mut ImageContext {
+        self.image_context.get_or_insert_with(Default::default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BatchAnnotateImagesRequest;
+    use crate::{AnnotateRequestBuilder, Feature, ImageSource, LatLng};
+    use serde_json::json;
+
+    fn image() -> ImageSource {
+        ImageSource::Content("aGVsbG8=".to_string())
+    }
+
+    #[test]
+    fn annotate_request_builder() {
+        let request = AnnotateRequestBuilder::new(image())
+            .feature(Feature::DocumentTextDetection)
+            .feature(Feature::LabelDetection.max_results(5))
+            .build();
+
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            json!({
+                "image": { "content": "aGVsbG8=" },
+                "features": [
+                    { "type": "DOCUMENT_TEXT_DETECTION" },
+                    { "type": "LABEL_DETECTION", "maxResults": 5 }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn language_hints() {
+        let request = AnnotateRequestBuilder::new(image())
+            .feature(Feature::DocumentTextDetection)
+            .language_hints(["ja", "en"])
+            .build();
+
+        assert_eq!(
+            serde_json::to_value(&request).unwrap()["imageContext"],
+            json!({ "languageHints": ["ja", "en"] })
+        );
+    }
+
+    #[test]
+    fn crop_hints_aspect_ratios() {
+        let request = AnnotateRequestBuilder::new(image())
+            .feature(Feature::CropHints)
+            .crop_hints_aspect_ratios([1.5, 1.0])
+            .build();
+
+        assert_eq!(
+            serde_json::to_value(&request).unwrap()["imageContext"],
+            json!({ "cropHintsParams": { "aspectRatios": [1.5, 1.0] } })
+        );
+    }
+
+    #[test]
+    fn include_geo_results() {
+        let request = AnnotateRequestBuilder::new(image())
+            .feature(Feature::WebDetection)
+            .include_geo_results(true)
+            .build();
+
+        assert_eq!(
+            serde_json::to_value(&request).unwrap()["imageContext"],
+            json!({ "webDetectionParams": { "includeGeoResults": true } })
+        );
+    }
+
+    #[test]
+    fn lat_long_rect() {
+        let request = AnnotateRequestBuilder::new(image())
+            .feature(Feature::LandmarkDetection)
+            .lat_long_rect(
+                LatLng {
+                    latitude: 35.5,
+                    longitude: 139.5,
+                },
+                LatLng {
+                    latitude: 35.75,
+                    longitude: 139.875,
+                },
+            )
+            .build();
+
+        assert_eq!(
+            serde_json::to_value(&request).unwrap()["imageContext"],
+            json!({
+                "latLongRect": {
+                    "minLatLng": { "latitude": 35.5, "longitude": 139.5 },
+                    "maxLatLng": { "latitude": 35.75, "longitude": 139.875 }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn enable_text_detection_confidence_score() {
+        let request = AnnotateRequestBuilder::new(image())
+            .feature(Feature::TextDetection)
+            .enable_text_detection_confidence_score(true)
+            .build();
+
+        assert_eq!(
+            serde_json::to_value(&request).unwrap()["imageContext"],
+            json!({
+                "textDetectionParams": { "enableTextDetectionConfidenceScore": true }
+            })
+        );
+    }
+
+    #[test]
+    fn handwriting() {
+        let request = AnnotateRequestBuilder::new(image()).handwriting().build();
+
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            json!({
+                "image": { "content": "aGVsbG8=" },
+                "features": [
+                    { "type": "DOCUMENT_TEXT_DETECTION", "model": "builtin/latest" }
+                ],
+                "imageContext": { "languageHints": ["en-t-i0-handwrit"] }
+            })
+        );
+    }
+
+    #[test]
+    fn fields() {
+        let masked = AnnotateRequestBuilder::new(image())
+            .feature(Feature::TextDetection)
+            .fields("responses.textAnnotations.description")
+            .build();
+        assert_eq!(
+            serde_json::to_value(&masked).unwrap(),
+            json!({
+                "image": { "content": "aGVsbG8=" },
+                "features": [{ "type": "TEXT_DETECTION" }]
+            })
+        );
+
+        let labels = AnnotateRequestBuilder::new(image())
+            .feature(Feature::LabelDetection)
+            .fields("responses.labelAnnotations")
+            .build();
+        let requests = [masked.clone(), labels, masked.clone()];
+        assert_eq!(
+            BatchAnnotateImagesRequest {
+                requests: &requests
+            }
+            .fields()
+            .as_deref(),
+            Some("responses.textAnnotations.description,responses.labelAnnotations")
+        );
+
+        let unmasked = AnnotateRequestBuilder::new(image()).build();
+        assert_eq!(
+            BatchAnnotateImagesRequest {
+                requests: &[masked, unmasked]
+            }
+            .fields(),
+            None
+        );
+    }
+
+    #[test]
+    fn serialize_feature() {
+        assert_eq!(
+            serde_json::to_value(Feature::LabelDetection).unwrap(),
+            json!("LABEL_DETECTION")
+        );
+        assert_eq!(
+            serde_json::to_value(Feature::DocumentTextDetection).unwrap(),
+            json!("DOCUMENT_TEXT_DETECTION")
+        );
+        assert_eq!(
+            serde_json::to_value(Feature::TextDetection).unwrap(),
+            json!("TEXT_DETECTION")
+        );
+    }
+
+    #[test]
+    fn gcs_image_source() {
+        let request =
+            AnnotateRequestBuilder::new(ImageSource::GcsUri("gs://bucket/img.png".to_string()))
+                .feature(Feature::LabelDetection)
+                .build();
+
+        assert_eq!(
+            serde_json::to_value(&request).unwrap()["image"],
+            json!({ "source": { "gcsImageUri": "gs://bucket/img.png" } })
+        );
+    }
+
+    #[test]
+    fn image_uri_source() {
+        let request = AnnotateRequestBuilder::new(ImageSource::ImageUri(
+            "https://cdn.example.com/img.jpg".to_string(),
+        ))
+        .feature(Feature::LabelDetection)
+        .build();
+
+        assert_eq!(
+            serde_json::to_value(&request).unwrap()["image"],
+            json!({ "source": { "imageUri": "https://cdn.example.com/img.jpg" } })
+        );
+    }
+}
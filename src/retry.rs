@@ -0,0 +1,406 @@
+use crate::error::is_transient_status;
+use crate::{Error, HttpResponse};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, SystemTime};
+
+/// What an attempt of an API call ended with.
+#[derive(Debug, Copy, Clone)]
+pub enum Outcome<'a> {
+    /// A response other than a success.
+    Response(&'a HttpResponse),
+    /// The call failed without a response, e.g. the connection was reset.
+    Error(&'a Error),
+}
+
+impl Outcome<'_> {
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Outcome::Response(response) => Some(response.status),
+            Outcome::Error(_) => None,
+        }
+    }
+
+    /// Whether another attempt may succeed: HTTP 429, 500, 502 and 503, and the
+    /// [retryable](Error::is_retryable) errors such as refused connections.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Outcome::Response(response) => is_transient_status(response.status),
+            Outcome::Error(error) => error.is_retryable(),
+        }
+    }
+
+    /// How long the server asked to wait with `Retry-After`, given either in seconds or
+    /// as an HTTP date.
+    pub fn retry_after(&self) -> Option<Duration> {
+        let Outcome::Response(response) = self else {
+            return None;
+        };
+        let value = response.header("Retry-After")?.trim();
+        if let Ok(seconds) = value.parse::<u64>() {
+            return Some(Duration::from_secs(seconds));
+        }
+
+        let date = httpdate::parse_http_date(value).ok()?;
+        Some(
+            date.duration_since(SystemTime::now())
+                .unwrap_or(Duration::ZERO),
+        )
+    }
+}
+
+/// A retry a [`Client`](crate::Client) is about to make, passed to
+/// [`ClientBuilder::on_retry`](crate::ClientBuilder::on_retry).
+#[derive(Debug, Copy, Clone)]
+pub struct RetryEvent<'a> {
+    /// The number of the retry, counting from 1.
+    pub attempt: u32,
+    /// What the previous attempt ended with.
+    pub outcome: Outcome<'a>,
+    /// How long the client waits before the retry.
+    pub delay: Duration,
+}
+
+pub(crate) type RetryCallback = std::sync::Arc<dyn Fn(&RetryEvent<'_>) + Send + Sync>;
+
+/// Decides whether a [`Client`](crate::Client) tries a failed call again.
+///
+/// [`ExponentialBackoff`] suits most uses; implement this for e.g. deadline-aware or
+/// budget-based policies.
+pub trait RetryPolicy: Send + Sync {
+    /// How long to wait before retry number `retry`, counting from 0, after an attempt
+    /// ended with `outcome`. `None` gives up and returns `outcome` to the caller.
+    fn retry(&self, retry: u32, outcome: Outcome<'_>) -> Option<Duration>;
+}
+
+/// Fails on the first error, the default.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct NoRetry;
+
+impl RetryPolicy for NoRetry {
+    fn retry(&self, _: u32, _: Outcome<'_>) -> Option<Duration> {
+        None
+    }
+}
+
+/// Retries [transient](Outcome::is_transient) failures up to `max_retries` times.
+///
+/// The n-th retry waits a random time between half of and the full
+/// `initial_backoff * multiplier^n`, capped at `max_backoff`, unless the response says
+/// how long to wait with a `Retry-After` header. That wait is capped at `max_backoff`
+/// too, so a server can't stall the call for hours.
+/// ```no_run
+/// # fn main() -> anyhow::Result<()> {
+/// let client = gcv_client::Client::builder()
+///     .credential(gcv_client::Credential::access_token("ya29..."))
+///     .retry(gcv_client::ExponentialBackoff::new(5))
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ExponentialBackoff {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+}
+
+impl ExponentialBackoff {
+    /// Retries up to `max_retries` times, starting at 500ms and backing off up to 32s.
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(32),
+            multiplier: 2.0,
+        }
+    }
+
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// # Panics
+    /// If `multiplier` is NaN, infinite or negative.
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        assert!(
+            multiplier.is_finite() && multiplier >= 0.0,
+            "invalid backoff multiplier {}",
+            multiplier
+        );
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Time to wait before retry number `retry` without a `Retry-After`.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let max = self.max_backoff.as_secs_f64();
+        let backoff = self.initial_backoff.as_secs_f64() * self.multiplier.powf(retry.into());
+        // Also catches NaN and infinity, e.g. when the fields were set directly.
+        let backoff = if backoff < max { backoff.max(0.0) } else { max };
+
+        // `max` may round up past `Duration::MAX`.
+        Duration::try_from_secs_f64(backoff * (0.5 + 0.5 * random())).unwrap_or(self.max_backoff)
+    }
+}
+
+impl RetryPolicy for ExponentialBackoff {
+    fn retry(&self, retry: u32, outcome: Outcome<'_>) -> Option<Duration> {
+        if retry >= self.max_retries || !outcome.is_transient() {
+            return None;
+        }
+
+        match outcome.retry_after() {
+            Some(delay) => Some(delay.min(self.max_backoff)),
+            None => Some(self.backoff(retry)),
+        }
+    }
+}
+
+/// A number in `[0, 1)`, random enough for jitter.
+fn random() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(all(test, feature = "reqwest"))]
+mod tests {
+    use super::{ExponentialBackoff, Outcome, RetryPolicy};
+    use crate::test_server::{image_request, TestServer};
+    use crate::{Client, Credential, HttpRequest, HttpResponse, HttpTransport};
+    use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, SystemTime};
+
+    fn response(status: u16, headers: &[(&str, &str)]) -> HttpResponse {
+        HttpResponse {
+            status,
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            body: br#"{"responses": [{}]}"#.to_vec(),
+        }
+    }
+
+    /// Answers with 429 and `Retry-After: 0` once, then with 200.
+    #[derive(Default)]
+    struct Throttling(Mutex<usize>);
+
+    #[async_trait]
+    impl HttpTransport for Throttling {
+        async fn send(&self, _: HttpRequest) -> crate::Result<HttpResponse> {
+            let mut calls = self.0.lock().unwrap();
+            *calls += 1;
+            if *calls == 1 {
+                Ok(response(429, &[("retry-after", "0")]))
+            } else {
+                Ok(response(200, &[]))
+            }
+        }
+    }
+
+    #[test]
+    fn backoff() {
+        let policy = ExponentialBackoff::new(5)
+            .initial_backoff(Duration::from_secs(1))
+            .max_backoff(Duration::from_secs(5));
+
+        for _ in 0..100 {
+            let first = policy.backoff(0);
+            assert!(first >= Duration::from_millis(500) && first <= Duration::from_secs(1));
+            let third = policy.backoff(2);
+            assert!(third >= Duration::from_secs(2) && third <= Duration::from_secs(4));
+            let capped = policy.backoff(10);
+            assert!(capped >= Duration::from_millis(2500) && capped <= Duration::from_secs(5));
+        }
+    }
+
+    #[test]
+    fn backoff_overflow() {
+        assert!(ExponentialBackoff::new(100).backoff(80) <= Duration::from_secs(32));
+        assert!(ExponentialBackoff::new(100).backoff(u32::MAX) <= Duration::from_secs(32));
+
+        let policy = ExponentialBackoff::new(5).max_backoff(Duration::MAX);
+        assert!(policy.backoff(2000) > Duration::from_secs(1));
+
+        let policy = ExponentialBackoff {
+            multiplier: f64::NAN,
+            ..ExponentialBackoff::new(5)
+        };
+        assert!(policy.backoff(1) <= Duration::from_secs(32));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid backoff multiplier")]
+    fn invalid_multiplier() {
+        let _ = ExponentialBackoff::new(5).multiplier(-1.0);
+    }
+
+    #[test]
+    fn transient_error() {
+        let reset = std::io::Error::from(std::io::ErrorKind::ConnectionReset);
+        let error = crate::Error::http(reset);
+        assert!(Outcome::Error(&error).is_transient());
+        assert!(!Outcome::Error(&crate::Error::decode("invalid json")).is_transient());
+        assert!(Outcome::Response(&response(503, &[])).is_transient());
+        assert!(!Outcome::Response(&response(400, &[])).is_transient());
+    }
+
+    fn retry_after(response: &HttpResponse) -> Option<Duration> {
+        Outcome::Response(response).retry_after()
+    }
+
+    #[test]
+    fn parse_retry_after() {
+        assert_eq!(
+            retry_after(&response(429, &[("Retry-After", "120")])),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(retry_after(&response(429, &[])), None);
+        assert_eq!(
+            retry_after(&response(429, &[("Retry-After", "soon")])),
+            None
+        );
+
+        let date = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(60));
+        let delay = retry_after(&response(503, &[("retry-after", &date)])).unwrap();
+        assert!(delay > Duration::from_secs(50) && delay <= Duration::from_secs(60));
+        let past = retry_after(&response(
+            503,
+            &[("Retry-After", "Sun, 06 Nov 1994 08:49:37 GMT")],
+        ));
+        assert_eq!(past, Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn caps_retry_after() {
+        let policy = ExponentialBackoff::new(1).max_backoff(Duration::from_secs(10));
+        let throttled = response(429, &[("Retry-After", "3600")]);
+
+        assert_eq!(
+            policy.retry(0, Outcome::Response(&throttled)),
+            Some(Duration::from_secs(10))
+        );
+    }
+
+    #[tokio::test]
+    async fn honors_retry_after() {
+        let transport = Arc::new(Throttling::default());
+        let delays = Arc::new(Mutex::new(vec![]));
+        let observed = delays.clone();
+        let client = Client::builder()
+            .credential(Credential::access_token("TOKEN"))
+            .transport(transport.clone())
+            // would time out the test if Retry-After was ignored
+            .retry(ExponentialBackoff::new(1).initial_backoff(Duration::from_secs(600)))
+            .on_throttle(move |delay| observed.lock().unwrap().push(delay))
+            .build()
+            .unwrap();
+        let request = image_request();
+
+        client.annotate(&request).await.unwrap();
+        assert_eq!(*transport.0.lock().unwrap(), 2);
+        assert_eq!(*delays.lock().unwrap(), [Duration::ZERO]);
+    }
+
+    #[tokio::test]
+    async fn retries_transient_errors() {
+        let server = TestServer::start(vec![
+            (503, r#"{"error": {"code": 503}}"#.to_string()),
+            (429, r#"{"error": {"code": 429}}"#.to_string()),
+            (200, r#"{"responses": [{}]}"#.to_string()),
+        ])
+        .await;
+        let client = server
+            .client_builder()
+            .retry(ExponentialBackoff::new(2).initial_backoff(Duration::from_millis(1)))
+            .build()
+            .unwrap();
+        let request = image_request();
+
+        client.annotate(&request).await.unwrap();
+        assert_eq!(server.requests().await.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn on_retry() {
+        let server = TestServer::start(vec![
+            (503, r#"{"error": {"code": 503}}"#.to_string()),
+            (200, r#"{"responses": [{}]}"#.to_string()),
+        ])
+        .await;
+        let events = Arc::new(Mutex::new(vec![]));
+        let observed = events.clone();
+        let client = server
+            .client_builder()
+            .retry(Once)
+            .on_retry(move |event| {
+                observed
+                    .lock()
+                    .unwrap()
+                    .push((event.attempt, event.outcome.status(), event.delay))
+            })
+            .build()
+            .unwrap();
+        let request = image_request();
+
+        client.annotate(&request).await.unwrap();
+        assert_eq!(*events.lock().unwrap(), [(1, Some(503), Duration::ZERO)]);
+    }
+
+    /// Retries anything once, right away.
+    struct Once;
+
+    impl RetryPolicy for Once {
+        fn retry(&self, retry: u32, _: Outcome<'_>) -> Option<Duration> {
+            (retry == 0).then_some(Duration::ZERO)
+        }
+    }
+
+    #[tokio::test]
+    async fn custom_policy() {
+        let server = TestServer::start(vec![
+            (400, r#"{"error": {"code": 400}}"#.to_string()),
+            (200, r#"{"responses": [{}]}"#.to_string()),
+        ])
+        .await;
+        let client = server.client_builder().retry(Once).build().unwrap();
+        let request = image_request();
+
+        client.annotate(&request).await.unwrap();
+        assert_eq!(server.requests().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn gives_up() {
+        let server = TestServer::start(vec![
+            (500, r#"{"error": {"code": 500}}"#.to_string()),
+            (502, r#"{"error": {"code": 502}}"#.to_string()),
+            (400, r#"{"error": {"code": 400}}"#.to_string()),
+            (200, r#"{"responses": [{}]}"#.to_string()),
+        ])
+        .await;
+        let client = server
+            .client_builder()
+            .retry(ExponentialBackoff::new(1).initial_backoff(Duration::from_millis(1)))
+            .build()
+            .unwrap();
+        let request = image_request();
+
+        assert!(client.annotate(&request).await.is_err());
+        assert_eq!(server.requests().await.len(), 2);
+
+        // 400 is not retried
+        assert!(client.annotate(&request).await.is_err());
+        assert_eq!(server.requests().await.len(), 3);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{Rect, Response};
+use std::ops::Range;
+
+/// Text found in a [`Response`], with the box enclosing the words it spans.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextMatch {
+    pub text: String,
+    pub bounding_box: Rect,
+}
+
+impl Response {
+    /// Finds every occurrence of `needle` in the text, e.g. `"TOTAL"`, even across words.
+    pub fn find_text(&self, needle: &str) -> Vec<TextMatch> {
+        self.find_with(|text| {
+            if needle.is_empty() {
+                return vec![];
+            }
+            text.match_indices(needle)
+                .map(|(start, _)| start..start + needle.len())
+                .collect()
+        })
+    }
+
+    /// Finds the byte ranges `matcher` returns for the text, e.g. the matches of a regex
+    /// with `|text| re.find_iter(text).map(|m| m.range()).collect()`.
+    /// ```no_run
+    /// # fn f(response: gcv_client::Response) {
+    /// // ignoring case
+    /// let totals = response.find_with(|text| {
+    ///     let text = text.to_ascii_lowercase();
+    ///     text.match_indices("total").map(|(i, m)| i..i + m.len()).collect()
+    /// });
+    /// for found in totals {
+    ///     println!("{} at {:?}", found.text, found.bounding_box);
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// The text is that of `DOCUMENT_TEXT_DETECTION`, with paragraphs and blocks on lines
+    /// of their own, or of the words of `TEXT_DETECTION` separated by spaces.
+    pub fn find_with(&self, matcher: impl Fn(&str) -> Vec<Range<usize>>) -> Vec<TextMatch> {
+        let (text, words) = self.searchable_text();
+
+        matcher(&text)
+            .into_iter()
+            .filter_map(|range| {
+                let bounding_box = words
+                    .iter()
+                    .filter(|(word, _)| word.start < range.end && range.start < word.end)
+                    .map(|(_, rect)| *rect)
+                    .reduce(|a, b| a.union(&b))?;
+                Some(TextMatch {
+                    text: text.get(range)?.to_string(),
+                    bounding_box,
+                })
+            })
+            .collect()
+    }
+
+    /// The text to search, with the byte range and box of each word in it.
+    fn searchable_text(&self) -> (String, Vec<(Range<usize>, Rect)>) {
+        let mut text = String::new();
+        let mut words = vec![];
+
+        let annotations = self.annotations();
+        if let Some(full_text) = &annotations.full_text_annotation {
+            for paragraph in full_text.paragraphs() {
+                // The API often leaves out the break after the last word of a paragraph
+                // or block, which would join it with the next word.
+                if !text.is_empty() && !text.ends_with(char::is_whitespace) {
+                    text.push('\n');
+                }
+                for word in &paragraph.words {
+                    let start = text.len();
+                    text.push_str(&word.text());
+                    words.push((start..text.len(), word.bounding_box.rect()));
+                    text.push_str(word.symbols.last().map_or("", |s| s.break_text()));
+                }
+            }
+        } else {
+            // The first annotation is all of the text, the others are its words.
+            for word in annotations.text_annotations.iter().skip(1) {
+                if !text.is_empty() {
+                    text.push(' ');
+                }
+                let start = text.len();
+                text.push_str(&word.description);
+                words.push((start..text.len(), word.bounding_poly.rect()));
+            }
+        }
+
+        (text, words)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_fixtures::{full_text_json, word};
+    use crate::{Rect, Response};
+    use serde_json::json;
+
+    #[test]
+    fn find_text() {
+        let word = |text: &str, left: i64| {
+            json!({
+                "description": text,
+                "boundingPoly": { "vertices": [
+                    { "x": left, "y": 10 }, { "x": left + 40, "y": 10 },
+                    { "x": left + 40, "y": 20 }, { "x": left, "y": 20 }
+                ] }
+            })
+        };
+        let response = Response::from_json_str(
+            &json!({
+                "textAnnotations": [
+                    word("TOTAL DUE 2024-05-01", 0),
+                    word("TOTAL", 0),
+                    word("DUE", 50),
+                    word("2024-05-01", 100)
+                ]
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let found = response.find_text("TOTAL DUE");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].bounding_box, Rect::new(0, 10, 90, 20));
+
+        let dates = response.find_with(|text| {
+            text.find('-')
+                .map(|dash| dash - 4..dash + 6)
+                .into_iter()
+                .collect()
+        });
+        assert_eq!(dates[0].text, "2024-05-01");
+        assert_eq!(dates[0].bounding_box, Rect::new(100, 10, 140, 20));
+        assert!(response.find_text("missing").is_empty());
+    }
+
+    #[test]
+    fn paragraph_boundaries() {
+        // Words without a break at the end of their paragraph.
+        let word = |text: &str, left: i64| word(text, Rect::new(left, 10, left + 40, 20));
+        let mut full_text = full_text_json(vec![
+            vec![vec![word("TOTAL", 0)], vec![word("DUE", 50)]],
+            vec![vec![word("12.00", 100)]],
+        ]);
+        full_text["text"] = json!("TOTAL\nDUE\n12.00\n");
+        let response = Response::new(json!({ "fullTextAnnotation": full_text }), "").unwrap();
+
+        assert!(response.find_text("TOTALDUE").is_empty());
+        assert!(response.find_text("DUE12").is_empty());
+        let found = response.find_text("TOTAL\nDUE");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].bounding_box, Rect::new(0, 10, 90, 20));
+    }
+}
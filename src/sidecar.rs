@@ -0,0 +1,116 @@
+//! Saves responses as JSON files next to their images, e.g. `photo.png` and
+//! `photo.gcv.json`, to cache results or share them without calling the API again.
+
+use crate::{Error, Response, Result};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+/// Version of the file format written by [`Response::save`].
+pub const SIDECAR_VERSION: u64 = 1;
+
+/// Where the response for the image at `image` is saved: `photo.png` becomes
+/// `photo.gcv.json`.
+pub fn sidecar_path(image: impl AsRef<Path>) -> PathBuf {
+    image.as_ref().with_extension("gcv.json")
+}
+
+impl Response {
+    /// Saves the response to `path`, along with the version of the format.
+    /// ```no_run
+    /// # async fn f(client: gcv_client::Client) -> anyhow::Result<()> {
+    /// use gcv_client::{sidecar_path, ImageGCV, Response};
+    ///
+    /// let response = match Response::load_sidecar("photo.png")? {
+    ///     Some(response) => response,
+    ///     None => {
+    ///         let response = client.request(&ImageGCV::from_path("photo.png")?).await?;
+    ///         response.save(sidecar_path("photo.png"))?;
+    ///         response
+    ///     }
+    /// };
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let json = json!({
+            "version": SIDECAR_VERSION,
+            "response": self.raw(),
+        });
+        let json = serde_json::to_vec_pretty(&json).map_err(Error::encode)?;
+
+        std::fs::write(path, json).map_err(|error| {
+            Error::encode(
+                anyhow::Error::new(error).context(format!("failed to write {}", path.display())),
+            )
+        })
+    }
+
+    /// Reads a response written by [`Response::save`].
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|error| {
+            Error::decode(
+                anyhow::Error::new(error).context(format!("failed to read {}", path.display())),
+            )
+        })?;
+
+        let mut saved: Value = serde_json::from_str(&json)?;
+        match saved["version"].as_u64() {
+            Some(version) if version <= SIDECAR_VERSION => {}
+            version => {
+                return Err(Error::decode(format!(
+                    "{} has unsupported version {:?}",
+                    path.display(),
+                    version
+                )))
+            }
+        }
+        Self::new(saved["response"].take(), "/response")
+    }
+
+    /// Reads the response saved next to the image at `image`, if there is one.
+    pub fn load_sidecar(image: impl AsRef<Path>) -> Result<Option<Self>> {
+        let path = sidecar_path(image);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        Self::load(path).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sidecar_path;
+    use crate::Response;
+    use std::path::Path;
+
+    #[test]
+    fn save_and_load() {
+        let dir = std::env::temp_dir().join(format!("gcv-sidecar-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let image = dir.join("photo.png");
+        assert_eq!(sidecar_path(&image), dir.join("photo.gcv.json"));
+        assert!(Response::load_sidecar(&image).unwrap().is_none());
+
+        let response = Response::from_json_str(
+            r#"{"labelAnnotations": [{"description": "Cat", "score": 0.9}]}"#,
+        )
+        .unwrap();
+        response.save(sidecar_path(&image)).unwrap();
+
+        let loaded = Response::load_sidecar(&image).unwrap().unwrap();
+        assert_eq!(loaded.raw(), response.raw());
+        assert_eq!(loaded.label_annotations()[0].description, "Cat");
+
+        std::fs::write(sidecar_path(&image), r#"{"version": 99, "response": {}}"#).unwrap();
+        assert!(Response::load_sidecar(&image).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            sidecar_path("scans/page.1.tiff"),
+            Path::new("scans/page.1.gcv.json")
+        );
+    }
+}
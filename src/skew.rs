@@ -0,0 +1,123 @@
+use crate::FullTextAnnotation;
+
+/// How far the text of a document is rotated, to deskew the image before running OCR
+/// again.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Skew {
+    /// The clockwise rotation of the text in degrees, in `-180.0..=180.0`. Rotating the
+    /// image by the opposite angle makes the text upright.
+    pub angle: f64,
+    /// The quarter turn nearest to `angle`.
+    pub orientation: Orientation,
+    /// What is left of `angle` after the quarter turn, in `-45.0..=45.0`, e.g. from a
+    /// scan that was fed in crooked.
+    pub residual: f64,
+}
+
+/// The rotation of a document in quarter turns, clockwise.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Orientation {
+    Upright,
+    Clockwise90,
+    UpsideDown,
+    Clockwise270,
+}
+
+impl Orientation {
+    pub fn degrees(&self) -> u32 {
+        match self {
+            Orientation::Upright => 0,
+            Orientation::Clockwise90 => 90,
+            Orientation::UpsideDown => 180,
+            Orientation::Clockwise270 => 270,
+        }
+    }
+}
+
+impl FullTextAnnotation {
+    /// Estimates the dominant rotation of the text from the top edges of the words,
+    /// weighting long words more. `None` without words.
+    /// ```no_run
+    /// # fn f(full_text: gcv_client::FullTextAnnotation) {
+    /// if let Some(skew) = full_text.skew() {
+    ///     if skew.angle.abs() > 0.5 {
+    ///         println!("rotate the image by {:.1}° and try again", -skew.angle);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn skew(&self) -> Option<Skew> {
+        // Averaging unit vectors rather than degrees keeps angles around ±180° together.
+        let (sin, cos) = self
+            .words()
+            .map(|word| {
+                let rect = word.bounding_box.rect();
+                let weight = rect.width().max(rect.height());
+                let angle = word.bounding_box.angle().to_radians();
+                (weight as f64 * angle.sin(), weight as f64 * angle.cos())
+            })
+            .fold(None, |sum: Option<(f64, f64)>, (sin, cos)| {
+                let (s, c) = sum.unwrap_or_default();
+                Some((s + sin, c + cos))
+            })?;
+
+        let angle = if sin == 0.0 && cos == 0.0 {
+            0.0
+        } else {
+            sin.atan2(cos).to_degrees()
+        };
+        let quarter_turns = (angle / 90.0).round();
+        let orientation = match quarter_turns.rem_euclid(4.0) as u32 {
+            1 => Orientation::Clockwise90,
+            2 => Orientation::UpsideDown,
+            3 => Orientation::Clockwise270,
+            _ => Orientation::Upright,
+        };
+
+        Some(Skew {
+            angle,
+            orientation,
+            residual: angle - quarter_turns * 90.0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Orientation;
+    use crate::test_fixtures::{full_text, rotated_word};
+    use serde_json::Value;
+
+    /// A word whose top edge runs from `(x, y)` by `(dx, dy)`, 10 pixels high.
+    fn word(x: i64, y: i64, dx: i64, dy: i64) -> Value {
+        rotated_word(
+            "w",
+            [
+                (x, y),
+                (x + dx, y + dy),
+                (x + dx - dy / 10, y + dy + dx / 10),
+                (x - dy / 10, y + dx / 10),
+            ],
+        )
+    }
+
+    #[test]
+    fn skew() {
+        // Text turned clockwise by a quarter, and then a little more.
+        let skew = full_text(vec![word(500, 0, -5, 100), word(400, 0, -4, 80)])
+            .skew()
+            .unwrap();
+        assert_eq!(skew.orientation, Orientation::Clockwise90);
+        assert_eq!(skew.orientation.degrees(), 90);
+        assert!((skew.residual - 2.86).abs() < 0.01, "{:?}", skew);
+
+        // Upside down, with words just either side of 180°.
+        let skew = full_text(vec![word(100, 50, -100, 2), word(300, 50, -100, -2)])
+            .skew()
+            .unwrap();
+        assert_eq!(skew.orientation, Orientation::UpsideDown);
+        assert!(skew.residual.abs() < 1e-9);
+
+        assert!(full_text(vec![]).skew().is_none());
+    }
+}
@@ -0,0 +1,116 @@
+use crate::geometry::edges;
+use crate::{FullTextAnnotation, Point, Polygon, Rect, Response, TextAnnotation, Word};
+
+impl FullTextAnnotation {
+    /// The words lying entirely within `rect`, e.g. the text in a region of a screenshot.
+    pub fn words_in_rect(&self, rect: Rect) -> impl Iterator<Item = &Word> + '_ {
+        self.words()
+            .filter(move |word| rect.contains(&word.bounding_box.rect()))
+    }
+
+    /// The words overlapping `rect`, even partly.
+    pub fn words_intersecting(&self, rect: Rect) -> impl Iterator<Item = &Word> + '_ {
+        self.words()
+            .filter(move |word| rect.intersects(&word.bounding_box.rect()))
+    }
+
+    /// The word whose box is closest to `point`, with the distance to it, 0 if the point
+    /// is on the word, e.g. to select the text under a click. `None` without words.
+    pub fn nearest_word(&self, point: Point) -> Option<(&Word, f64)> {
+        self.words()
+            .map(|word| (word, word.bounding_box.rect().distance_to(point)))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+}
+
+impl Response {
+    /// The words of `TEXT_DETECTION` whose polygon overlaps `polygon`. The first text
+    /// annotation, which spans all of the text, is left out.
+    pub fn text_annotations_intersecting(&self, polygon: &Polygon) -> Vec<&TextAnnotation> {
+        self.annotations()
+            .text_annotations
+            .iter()
+            .skip(1)
+            .filter(|text| text.bounding_poly.intersects(polygon))
+            .collect()
+    }
+}
+
+impl Polygon {
+    /// Whether the pixel vertices of both polygons overlap, assuming both are convex like
+    /// the boxes of the API.
+    pub fn intersects(&self, other: &Polygon) -> bool {
+        if self.vertices.is_empty() || other.vertices.is_empty() {
+            return false;
+        }
+
+        // Separating axis theorem: convex polygons are disjoint iff the projections on
+        // the normal of some edge don't overlap.
+        [&self.vertices, &other.vertices]
+            .into_iter()
+            .flat_map(|vertices| edges(vertices))
+            .all(|(a, b)| {
+                let normal = (a.y - b.y, b.x - a.x);
+                let (min_a, max_a) = project(&self.vertices, normal);
+                let (min_b, max_b) = project(&other.vertices, normal);
+                min_a <= max_b && min_b <= max_a
+            })
+    }
+}
+
+fn project(vertices: &[Point], (nx, ny): (i64, i64)) -> (i64, i64) {
+    let dots = vertices.iter().map(|vertex| vertex.x * nx + vertex.y * ny);
+    (dots.clone().min().unwrap_or(0), dots.max().unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_fixtures::{full_text, word};
+    use crate::{FullTextAnnotation, Point, Polygon, Rect};
+
+    fn polygon(vertices: &[(i64, i64)]) -> Polygon {
+        Polygon {
+            vertices: vertices.iter().map(|&(x, y)| Point { x, y }).collect(),
+            normalized_vertices: vec![],
+        }
+    }
+
+    #[test]
+    fn intersects() {
+        let square = polygon(&[(0, 0), (10, 0), (10, 10), (0, 10)]);
+        let overlapping = polygon(&[(5, 5), (15, 5), (15, 15), (5, 15)]);
+        // rotated by 45°: its box overlaps the square, but it stays off the corner
+        let diamond = polygon(&[(6, 16), (16, 6), (26, 16), (16, 26)]);
+
+        assert!(square.intersects(&overlapping));
+        assert!(!square.intersects(&diamond));
+        assert!(Rect::new(0, 0, 10, 10).intersects(&diamond.rect()));
+
+        let rect = Rect::new(0, 0, 100, 100);
+        assert!(rect.contains(&Rect::new(10, 10, 100, 20)));
+        assert!(!rect.contains(&Rect::new(90, 10, 110, 20)));
+        assert!(rect.intersects(&Rect::new(90, 10, 110, 20)));
+    }
+
+    #[test]
+    fn nearest_word() {
+        let full_text = full_text(vec![
+            word("left", Rect::new(0, 10, 40, 20)),
+            word("right", Rect::new(100, 10, 140, 20)),
+        ]);
+
+        let (word, distance) = full_text.nearest_word(Point { x: 20, y: 15 }).unwrap();
+        assert_eq!((word.text().as_str(), distance), ("left", 0.0));
+
+        let (word, distance) = full_text.nearest_word(Point { x: 100, y: 50 }).unwrap();
+        assert_eq!((word.text().as_str(), distance), ("right", 30.0));
+
+        let words: Vec<_> = full_text.words_in_rect(Rect::new(90, 0, 200, 30)).collect();
+        assert_eq!(words.len(), 1);
+        let empty = FullTextAnnotation {
+            text: String::new(),
+            pages: vec![],
+        };
+        assert!(empty.nearest_word(Point::default()).is_none());
+    }
+}
@@ -0,0 +1,61 @@
+//! OCR results to test the analyses of full text annotations with.
+
+use crate::{FullTextAnnotation, Rect};
+use serde_json::{json, Value};
+
+/// The JSON of a word of one symbol, both spanning `rect`.
+pub(crate) fn word(text: &str, rect: Rect) -> Value {
+    let Rect {
+        left,
+        top,
+        right,
+        bottom,
+    } = rect;
+    rotated_word(
+        text,
+        [(left, top), (right, top), (right, bottom), (left, bottom)],
+    )
+}
+
+/// The JSON of a word of one symbol, both with `vertices` as their box.
+pub(crate) fn rotated_word(text: &str, vertices: [(i64, i64); 4]) -> Value {
+    let vertices: Vec<_> = vertices
+        .iter()
+        .map(|&(x, y)| json!({ "x": x, "y": y }))
+        .collect();
+    let bounding_box = json!({ "vertices": vertices });
+    json!({
+        "boundingBox": bounding_box,
+        "symbols": [{ "boundingBox": bounding_box, "text": text }]
+    })
+}
+
+/// A page with one block of one paragraph of `words`, given as JSON like that of [`word`].
+pub(crate) fn full_text(words: Vec<Value>) -> FullTextAnnotation {
+    full_text_of_blocks(vec![vec![words]])
+}
+
+/// A page of blocks of paragraphs of words.
+pub(crate) fn full_text_of_blocks(blocks: Vec<Vec<Vec<Value>>>) -> FullTextAnnotation {
+    serde_json::from_value(full_text_json(blocks)).unwrap()
+}
+
+/// The JSON of [`full_text_of_blocks`], to add fields to before parsing it.
+pub(crate) fn full_text_json(blocks: Vec<Vec<Vec<Value>>>) -> Value {
+    let bounding_box = json!({ "vertices": [{}, {}, {}, {}] });
+    let blocks: Vec<_> = blocks
+        .into_iter()
+        .map(|paragraphs| {
+            let paragraphs: Vec<_> = paragraphs
+                .into_iter()
+                .map(|words| json!({ "boundingBox": bounding_box, "words": words }))
+                .collect();
+            json!({
+                "blockType": "TEXT",
+                "boundingBox": bounding_box,
+                "paragraphs": paragraphs
+            })
+        })
+        .collect();
+    json!({ "pages": [{ "blocks": blocks }] })
+}
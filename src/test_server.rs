@@ -0,0 +1,151 @@
+//! A minimal HTTP/1.1 server on localhost for testing the client without the network.
+// The client can only reach it with the reqwest transport.
+#![cfg_attr(not(feature = "reqwest"), allow(dead_code))]
+
+use crate::{
+    AnnotateImageRequest, AnnotateRequestBuilder, Client, ClientBuilder, Credential, ImageSource,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+
+pub(crate) struct TestServer {
+    addr: std::net::SocketAddr,
+    requests: Arc<Mutex<Vec<String>>>,
+    connections: Arc<AtomicUsize>,
+}
+
+impl TestServer {
+    /// Answers the n-th request with the n-th `(status, body)` and records every request.
+    /// Requests beyond the canned responses get a 500.
+    pub(crate) async fn start(responses: Vec<(u16, String)>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(vec![]));
+
+        let connections = Arc::new(AtomicUsize::new(0));
+
+        let recorded = requests.clone();
+        let accepted = connections.clone();
+        let responses = Arc::new(Mutex::new(responses.into_iter()));
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                accepted.fetch_add(1, Ordering::SeqCst);
+                // Connections are served concurrently, as a client may open a new one
+                // before it returns the last to its pool.
+                let recorded = recorded.clone();
+                let responses = responses.clone();
+                tokio::spawn(async move {
+                    loop {
+                        let Some(request) = read_request(&mut stream).await else {
+                            break;
+                        };
+                        let (status, body) = {
+                            let mut recorded = recorded.lock().await;
+                            recorded.push(request);
+                            responses
+                                .lock()
+                                .await
+                                .next()
+                                .unwrap_or((500, r#"{"error": {"code": 500}}"#.to_string()))
+                        };
+                        let response = format!(
+                            "HTTP/1.1 {} X\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                            status,
+                            body.len(),
+                            body
+                        );
+                        if stream.write_all(response.as_bytes()).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+
+        Self {
+            addr,
+            requests,
+            connections,
+        }
+    }
+
+    /// Accepts connections but never answers.
+    pub(crate) async fn start_silent() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let mut open = vec![];
+            while let Ok((stream, _)) = listener.accept().await {
+                open.push(stream);
+            }
+        });
+
+        Self {
+            addr,
+            requests: Arc::default(),
+            connections: Arc::default(),
+        }
+    }
+
+    pub(crate) fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// A builder for a client that sends requests to this server with the API key `KEY`.
+    pub(crate) fn client_builder(&self) -> ClientBuilder {
+        Client::builder()
+            .credential(Credential::ApiKey("KEY".to_string()))
+            .endpoint(&self.url())
+    }
+
+    pub(crate) fn client(&self) -> Client {
+        self.client_builder().build().unwrap()
+    }
+
+    /// Number of TCP connections accepted so far.
+    pub(crate) fn connections(&self) -> usize {
+        self.connections.load(Ordering::SeqCst)
+    }
+
+    /// The raw requests received so far, headers and body.
+    pub(crate) async fn requests(&self) -> Vec<String> {
+        self.requests.lock().await.clone()
+    }
+}
+
+/// A request for an image by URL, which the test server doesn't fetch.
+pub(crate) fn image_request() -> AnnotateImageRequest {
+    AnnotateRequestBuilder::new(ImageSource::ImageUri("https://example.com/a.png".into())).build()
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<String> {
+    let mut data = vec![];
+    let mut buf = [0; 8192];
+
+    loop {
+        let n = stream.read(&mut buf).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        data.extend_from_slice(&buf[..n]);
+
+        let text = String::from_utf8_lossy(&data);
+        if let Some(header_end) = text.find("\r\n\r\n") {
+            let content_length = text[..header_end]
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse::<usize>().ok())?
+                })
+                .unwrap_or(0);
+            if data.len() >= header_end + 4 + content_length {
+                return Some(String::from_utf8_lossy(&data).into_owned());
+            }
+        }
+    }
+}
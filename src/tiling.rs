@@ -0,0 +1,257 @@
+use crate::{
+    AnnotateRequestBuilder, Client, EncodeAs, Error, Feature, ImageGCV, ImageSource, Result,
+    TextAnnotation, Transform, MAX_BATCH_SIZE, MAX_REQUEST_SIZE,
+};
+use image::DynamicImage;
+
+/// Options for [`Client::request_tiled`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TileOptions {
+    /// Edge length of a square tile in pixels.
+    pub tile_size: u32,
+    /// Overlap between neighbouring tiles in pixels. Words longer than this
+    /// may be cut at a tile border and come back split.
+    pub overlap: u32,
+    pub encode_as: EncodeAs,
+}
+
+impl Default for TileOptions {
+    fn default() -> Self {
+        Self {
+            tile_size: 4096,
+            overlap: 256,
+            encode_as: EncodeAs::Png,
+        }
+    }
+}
+
+/// A tile of the original image. Words whose center lies in `core` belong to this tile,
+/// which splits every overlap in half between its two tiles.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Tile {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    core: [f64; 4],
+}
+
+/// Start offsets of tiles covering `len` pixels, the last one aligned to the end.
+fn starts(len: u32, tile_size: u32, overlap: u32) -> Vec<u32> {
+    if len <= tile_size {
+        return vec![0];
+    }
+
+    let step = tile_size - overlap;
+    let mut starts = vec![];
+    let mut start = 0;
+    while start + tile_size < len {
+        starts.push(start);
+        start += step;
+    }
+    starts.push(len - tile_size);
+    starts
+}
+
+/// `[from, to)` of each tile's core along one axis.
+fn cores(starts: &[u32], tile_size: u32, len: u32) -> Vec<(f64, f64)> {
+    let borders: Vec<f64> = starts
+        .windows(2)
+        .map(|w| (w[1] as f64 + (w[0] + tile_size).min(len) as f64) / 2.0)
+        .collect();
+
+    (0..starts.len())
+        .map(|i| {
+            let from = if i == 0 { 0.0 } else { borders[i - 1] };
+            let to = borders.get(i).copied().unwrap_or(len as f64);
+            (from, to)
+        })
+        .collect()
+}
+
+fn tiles(width: u32, height: u32, tile_size: u32, overlap: u32) -> Vec<Tile> {
+    let xs = starts(width, tile_size, overlap);
+    let ys = starts(height, tile_size, overlap);
+    let x_cores = cores(&xs, tile_size, width);
+    let y_cores = cores(&ys, tile_size, height);
+
+    ys.iter()
+        .zip(&y_cores)
+        .flat_map(|(&y, &(top, bottom))| {
+            xs.iter()
+                .zip(&x_cores)
+                .map(move |(&x, &(left, right))| Tile {
+                    x,
+                    y,
+                    width: tile_size.min(width - x),
+                    height: tile_size.min(height - y),
+                    core: [left, top, right, bottom],
+                })
+        })
+        .collect()
+}
+
+/// Translates each tile's word annotations into original image coordinates and
+/// keeps every word only from the tile whose core contains its center.
+fn merge(tiles: &[Tile], annotations: Vec<Vec<TextAnnotation>>) -> Vec<TextAnnotation> {
+    tiles
+        .iter()
+        .zip(annotations)
+        .flat_map(|(tile, annotations)| {
+            let transform = Transform::translate(tile.x as f64, tile.y as f64);
+
+            annotations.into_iter().filter_map(move |annotation| {
+                let bounding_poly = annotation.bounding_poly.transform(&transform);
+                let vertices = &bounding_poly.vertices;
+                if vertices.is_empty() {
+                    return None;
+                }
+
+                let n = vertices.len() as f64;
+                let cx = vertices.iter().map(|p| p.x as f64).sum::<f64>() / n;
+                let cy = vertices.iter().map(|p| p.y as f64).sum::<f64>() / n;
+                let [left, top, right, bottom] = tile.core;
+                let in_core = (left..right).contains(&cx) && (top..bottom).contains(&cy);
+
+                in_core.then_some(TextAnnotation {
+                    bounding_poly,
+                    ..annotation
+                })
+            })
+        })
+        .collect()
+}
+
+impl Client {
+    /// OCRs a very large image as overlapping tiles and returns the word-level text
+    /// annotations in original image coordinates, with the duplicates from the
+    /// overlaps removed.
+    ///
+    /// Unlike [`Response::text_annotations`](crate::Response::text_annotations), the
+    /// result has no leading entry holding the whole text.
+    pub async fn request_tiled(
+        &self,
+        image: &DynamicImage,
+        options: &TileOptions,
+    ) -> Result<Vec<TextAnnotation>> {
+        if options.overlap >= options.tile_size {
+            return Err(Error::InvalidInput(format!(
+                "overlap ({}) must be smaller than tile_size ({})",
+                options.overlap, options.tile_size
+            )));
+        }
+
+        let tiles = tiles(
+            image.width(),
+            image.height(),
+            options.tile_size,
+            options.overlap,
+        );
+        let requests = tiles
+            .iter()
+            .map(|tile| {
+                let cropped = image.crop_imm(tile.x, tile.y, tile.width, tile.height);
+                let gcv_image = ImageGCV::from_image_as(&cropped, options.encode_as)?;
+
+                Ok(AnnotateRequestBuilder::new(gcv_image)
+                    .feature(Feature::DocumentTextDetection)
+                    .build())
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut annotations = Vec::with_capacity(tiles.len());
+        let mut batch_start = 0;
+        while batch_start < requests.len() {
+            let mut batch_end = batch_start;
+            let mut size = 0;
+            while batch_end < requests.len() && batch_end - batch_start < MAX_BATCH_SIZE {
+                let len = match &requests[batch_end].image {
+                    ImageSource::Content(content) => content.len(),
+                    _ => 0,
+                };
+                if batch_end > batch_start && size + len > MAX_REQUEST_SIZE {
+                    break;
+                }
+                size += len;
+                batch_end += 1;
+            }
+
+            for response in self
+                .annotate_batch(&requests[batch_start..batch_end])
+                .await?
+            {
+                let mut text_annotations = response
+                    .map_err(Error::Image)?
+                    .into_annotations()
+                    .text_annotations;
+                // The first entry is the whole text of the tile.
+                if !text_annotations.is_empty() {
+                    text_annotations.remove(0);
+                }
+                annotations.push(text_annotations);
+            }
+            batch_start = batch_end;
+        }
+
+        Ok(merge(&tiles, annotations))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge, starts, tiles};
+    use crate::{Point, Polygon, TextAnnotation};
+
+    fn word(description: &str, x: i64, y: i64, width: i64, height: i64) -> TextAnnotation {
+        TextAnnotation {
+            locale: None,
+            description: description.to_string(),
+            bounding_poly: Polygon {
+                vertices: vec![
+                    Point { x, y },
+                    Point { x: x + width, y },
+                    Point {
+                        x: x + width,
+                        y: y + height,
+                    },
+                    Point { x, y: y + height },
+                ],
+                normalized_vertices: vec![],
+            },
+            confidence: None,
+        }
+    }
+
+    #[test]
+    fn tile_starts() {
+        assert_eq!(starts(100, 200, 20), vec![0]);
+        assert_eq!(starts(200, 200, 20), vec![0]);
+        assert_eq!(starts(500, 200, 20), vec![0, 180, 300]);
+    }
+
+    #[test]
+    fn tile_grid() {
+        let tiles = tiles(300, 150, 200, 40);
+
+        assert_eq!(tiles.len(), 2);
+        assert_eq!((tiles[1].x, tiles[1].y), (100, 0));
+        assert_eq!((tiles[1].width, tiles[1].height), (200, 150));
+        // The overlap is 100..200, split at 150.
+        assert_eq!(tiles[0].core, [0.0, 0.0, 150.0, 150.0]);
+        assert_eq!(tiles[1].core, [150.0, 0.0, 300.0, 150.0]);
+    }
+
+    #[test]
+    fn merge_removes_overlap_duplicates() {
+        let tiles = tiles(300, 100, 200, 100);
+        let left = vec![word("LEFT", 10, 10, 40, 20), word("MID", 120, 10, 40, 20)];
+        // The same word seen by the right tile, which starts at x = 100.
+        let right = vec![word("MID", 20, 10, 40, 20), word("RIGHT", 150, 10, 40, 20)];
+
+        let merged = merge(&tiles, vec![left, right]);
+        let descriptions: Vec<_> = merged.iter().map(|a| a.description.as_str()).collect();
+
+        assert_eq!(descriptions, ["LEFT", "MID", "RIGHT"]);
+        assert_eq!(merged[2].bounding_poly.vertices[0], Point { x: 250, y: 10 });
+    }
+}
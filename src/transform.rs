@@ -0,0 +1,189 @@
+use crate::Point;
+
+/// An affine mapping between two image coordinate spaces.
+///
+/// [`ImageGCV`](crate::ImageGCV) records one that maps coordinates in the uploaded
+/// image back to the original image, so geometry returned by the API can be related
+/// to the file on disk after downscaling or EXIF rotation.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Transform {
+    // x' = a * x + b * y + c
+    // y' = d * x + e * y + f
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    e: f64,
+    f: f64,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        a: 1.0,
+        b: 0.0,
+        c: 0.0,
+        d: 0.0,
+        e: 1.0,
+        f: 0.0,
+    };
+
+    pub fn scale(sx: f64, sy: f64) -> Self {
+        Self {
+            a: sx,
+            e: sy,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn translate(dx: f64, dy: f64) -> Self {
+        Self {
+            c: dx,
+            f: dy,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Maps coordinates in an image resized to `uploaded_width` x `uploaded_height` back
+    /// to the original `width` x `height` image, e.g. after downscaling it before upload.
+    pub fn from_resize(uploaded_width: u32, uploaded_height: u32, width: u32, height: u32) -> Self {
+        Self::scale(
+            width as f64 / uploaded_width.max(1) as f64,
+            height as f64 / uploaded_height.max(1) as f64,
+        )
+    }
+
+    /// Maps coordinates of an image displayed with the given EXIF orientation (`1..=8`)
+    /// back to the stored pixels of a `width` x `height` image.
+    pub fn from_exif_orientation(orientation: u32, width: u32, height: u32) -> Self {
+        let (w, h) = (width as f64, height as f64);
+        let (a, b, c, d, e, f) = match orientation {
+            // flip horizontal
+            2 => (-1.0, 0.0, w, 0.0, 1.0, 0.0),
+            // rotate 180
+            3 => (-1.0, 0.0, w, 0.0, -1.0, h),
+            // flip vertical
+            4 => (1.0, 0.0, 0.0, 0.0, -1.0, h),
+            // transpose
+            5 => (0.0, 1.0, 0.0, 1.0, 0.0, 0.0),
+            // rotate 90 clockwise
+            6 => (0.0, 1.0, 0.0, -1.0, 0.0, h),
+            // transverse
+            7 => (0.0, -1.0, w, -1.0, 0.0, h),
+            // rotate 270 clockwise
+            8 => (0.0, -1.0, w, 1.0, 0.0, 0.0),
+            _ => return Self::IDENTITY,
+        };
+
+        Self { a, b, c, d, e, f }
+    }
+
+    /// Applies `self` first, then `next`.
+    pub fn then(&self, next: &Transform) -> Transform {
+        Transform {
+            a: next.a * self.a + next.b * self.d,
+            b: next.a * self.b + next.b * self.e,
+            c: next.a * self.c + next.b * self.f + next.c,
+            d: next.d * self.a + next.e * self.d,
+            e: next.d * self.b + next.e * self.e,
+            f: next.d * self.c + next.e * self.f + next.f,
+        }
+    }
+
+    /// The transform undoing `self`, or `None` if it collapses the plane, e.g. a scale by 0.
+    pub fn inverse(&self) -> Option<Transform> {
+        let det = self.a * self.e - self.b * self.d;
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+        let (a, b, d, e) = (self.e / det, -self.b / det, -self.d / det, self.a / det);
+        Some(Transform {
+            a,
+            b,
+            c: -(a * self.c + b * self.f),
+            d,
+            e,
+            f: -(d * self.c + e * self.f),
+        })
+    }
+
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.a * x + self.b * y + self.c,
+            self.d * x + self.e * y + self.f,
+        )
+    }
+
+    /// Applies the transform and rounds to the nearest pixel.
+    pub fn apply_point(&self, point: Point) -> Point {
+        let (x, y) = self.apply(point.x as f64, point.y as f64);
+
+        Point {
+            x: x.round() as i64,
+            y: y.round() as i64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Transform;
+    use crate::Point;
+
+    #[test]
+    fn then() {
+        let t = Transform::scale(2.0, 3.0).then(&Transform::translate(10.0, 20.0));
+
+        assert_eq!(t.apply(1.0, 1.0), (12.0, 23.0));
+        assert_eq!(Transform::IDENTITY.then(&t), t);
+    }
+
+    #[test]
+    fn exif_orientation_round_trip() {
+        // A 4x2 image rotated 90 degrees clockwise is displayed as 2x4.
+        let t = Transform::from_exif_orientation(6, 4, 2);
+
+        assert_eq!(t.apply_point(Point { x: 0, y: 0 }), Point { x: 0, y: 2 });
+        assert_eq!(t.apply_point(Point { x: 2, y: 4 }), Point { x: 4, y: 0 });
+
+        for orientation in 1..=8 {
+            let t = Transform::from_exif_orientation(orientation, 4, 2);
+            let corners = [(0.0, 0.0), (4.0, 0.0), (0.0, 2.0), (4.0, 2.0)];
+            let displayed = if orientation >= 5 {
+                (2.0, 4.0)
+            } else {
+                (4.0, 2.0)
+            };
+            let mapped = [
+                t.apply(0.0, 0.0),
+                t.apply(displayed.0, 0.0),
+                t.apply(0.0, displayed.1),
+                t.apply(displayed.0, displayed.1),
+            ];
+
+            for corner in corners {
+                assert!(mapped.contains(&corner), "orientation {}", orientation);
+            }
+        }
+    }
+
+    #[test]
+    fn from_resize() {
+        // A 4000x3000 photo uploaded at 1000x750.
+        let t = Transform::from_resize(1000, 750, 4000, 3000);
+        assert_eq!(
+            t.apply_point(Point { x: 250, y: 100 }),
+            Point { x: 1000, y: 400 }
+        );
+
+        let round_trip = t.then(&Transform::translate(5.0, 7.0));
+        let inverse = round_trip.inverse().unwrap();
+        assert_eq!(round_trip.then(&inverse), Transform::IDENTITY);
+        assert!(Transform::scale(0.0, 1.0).inverse().is_none());
+    }
+}
@@ -0,0 +1,196 @@
+use crate::{redact, Result};
+use async_trait::async_trait;
+
+/// The methods of the requests a [`Client`](crate::Client) sends.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Post,
+}
+
+/// A request sent by [`Client`](crate::Client): a POST with a JSON body to the API, or
+/// a request of a [`TokenProvider`](crate::TokenProvider) for an access token.
+///
+/// Its `Debug` output masks the credential and leaves out the body.
+#[derive(Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: String,
+    /// Headers including `Content-Type` and the credential.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// A POST of `body` as `application/json`.
+    pub fn post_json(url: impl Into<String>, body: Vec<u8>) -> Self {
+        Self {
+            method: Method::Post,
+            url: url.into(),
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body,
+        }
+    }
+
+    /// A POST of `fields` as `application/x-www-form-urlencoded`.
+    pub fn post_form(url: impl Into<String>, fields: &[(&str, &str)]) -> Self {
+        let body = url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(fields)
+            .finish();
+
+        Self {
+            method: Method::Post,
+            url: url.into(),
+            headers: vec![(
+                "Content-Type".to_string(),
+                "application/x-www-form-urlencoded".to_string(),
+            )],
+            body: body.into_bytes(),
+        }
+    }
+
+    pub fn get(url: impl Into<String>) -> Self {
+        Self {
+            method: Method::Get,
+            url: url.into(),
+            headers: vec![],
+            body: vec![],
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+impl std::fmt::Debug for HttpRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let headers: Vec<_> = self
+            .headers
+            .iter()
+            .map(|(name, value)| (name, redact::redacted_header(name, value)))
+            .collect();
+
+        f.debug_struct("HttpRequest")
+            .field("method", &self.method)
+            .field("url", &redact::redacted_url(&self.url))
+            .field("headers", &headers)
+            .field("body", &format_args!("{} bytes", self.body.len()))
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    /// Headers the client acts on, such as `Retry-After`. Transports may leave out others.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// The value of the header `name`, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Sends the requests of a [`Client`](crate::Client), including those for access
+/// tokens, so that another HTTP stack or a test double can be used instead of reqwest.
+#[async_trait]
+pub trait HttpTransport: Send + Sync {
+    /// Sends `request` and returns the response, whatever its status.
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse>;
+}
+
+#[cfg(feature = "reqwest")]
+#[async_trait]
+impl HttpTransport for reqwest::Client {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse> {
+        let method = match request.method {
+            Method::Get => reqwest::Method::GET,
+            Method::Post => reqwest::Method::POST,
+        };
+        let mut builder = self.request(method, &request.url).body(request.body);
+        for (name, value) in &request.headers {
+            builder = builder.header(name, value);
+        }
+
+        let response = builder.send().await.map_err(from_reqwest)?;
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+
+        Ok(HttpResponse {
+            status: response.status().as_u16(),
+            headers,
+            body: response.bytes().await.map_err(from_reqwest)?.to_vec(),
+        })
+    }
+}
+
+#[cfg(feature = "reqwest")]
+fn from_reqwest(mut error: reqwest::Error) -> crate::Error {
+    if let Some(url) = error.url_mut() {
+        redact::redact_url(url);
+    }
+    if error.is_timeout() {
+        crate::Error::Timeout
+    } else {
+        crate::Error::http(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HttpRequest, HttpResponse, HttpTransport};
+    use crate::test_server::image_request;
+    use crate::{Client, Credential};
+    use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<HttpRequest>>);
+
+    #[async_trait]
+    impl HttpTransport for Recorder {
+        async fn send(&self, request: HttpRequest) -> crate::Result<HttpResponse> {
+            self.0.lock().unwrap().push(request);
+
+            Ok(HttpResponse {
+                status: 200,
+                headers: vec![],
+                body: br#"{"responses": [{"labelAnnotations": []}]}"#.to_vec(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn custom_transport() {
+        let recorder = Arc::new(Recorder::default());
+        let client = Client::with_transport(recorder.clone(), Credential::access_token("TOKEN"));
+        let request = image_request();
+
+        let response = client.annotate(&request).await.unwrap();
+        assert!(response.label_annotations().is_empty());
+
+        let requests = recorder.0.lock().unwrap();
+        assert_eq!(
+            requests[0].url,
+            "https://vision.googleapis.com/v1/images:annotate"
+        );
+        assert_eq!(
+            requests[0].headers,
+            [
+                ("Content-Type".to_string(), "application/json".to_string()),
+                ("Authorization".to_string(), "Bearer TOKEN".to_string())
+            ]
+        );
+    }
+}